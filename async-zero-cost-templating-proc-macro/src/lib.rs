@@ -1,6 +1,37 @@
-use async_zero_cost_templating_proc_macro2::parse::top_level_parse;
+use async_zero_cost_templating_proc_macro2::{
+    intermediate::Fallibility,
+    parse::{top_level_parse, top_level_parse_attribute_value, top_level_parse_attributes},
+};
 
 #[proc_macro]
 pub fn html(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    top_level_parse(input.into()).into()
+    top_level_parse(input.into(), Fallibility::Infallible).into()
+}
+
+#[proc_macro]
+pub fn html_attributes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    top_level_parse_attributes(input.into(), Fallibility::Infallible).into()
+}
+
+#[proc_macro]
+pub fn html_attribute_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    top_level_parse_attribute_value(input.into(), Fallibility::Infallible).into()
+}
+
+/// Like `html!` but the template can fail with `?` and yields `Result<T, E>`.
+#[proc_macro]
+pub fn try_html(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    top_level_parse(input.into(), Fallibility::Fallible).into()
+}
+
+/// Like `html_attributes!` but the template can fail with `?` and yields `Result<T, E>`.
+#[proc_macro]
+pub fn try_html_attributes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    top_level_parse_attributes(input.into(), Fallibility::Fallible).into()
+}
+
+/// Like `html_attribute_value!` but the template can fail with `?` and yields `Result<T, E>`.
+#[proc_macro]
+pub fn try_html_attribute_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    top_level_parse_attribute_value(input.into(), Fallibility::Fallible).into()
 }
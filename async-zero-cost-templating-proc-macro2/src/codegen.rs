@@ -1,83 +1,189 @@
 use crate::{
-    intermediate::Intermediate,
-    parse::{HtmlForLoop, HtmlIf, HtmlWhile},
+    intermediate::{EscapeContext, Fallibility, Intermediate, TemplateContext},
+    parse::{
+        HtmlCondition, HtmlElse, HtmlForLoop, HtmlIf, HtmlLet, HtmlMatch, HtmlMatchArm, HtmlWhile,
+    },
 };
-use quote::{quote, quote_spanned};
+use proc_macro2::{Ident, Span};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::spanned::Spanned;
 
-pub fn top_level(input: Vec<Intermediate>) -> proc_macro2::TokenStream {
-    let inner = codegen(input);
-    quote! {
-        {
-            let (tx, rx) = ::tokio::sync::mpsc::channel(1);
-            let future = async move {
-                #inner
-            };
-            ::async_zero_cost_templating::TemplateToStream::new(future, rx)
+// mixed site hygiene so variables in the template can't shadow the sender
+fn tx() -> Ident {
+    Ident::new("tx", Span::mixed_site())
+}
+
+pub fn top_level(
+    context: TemplateContext,
+    fallibility: Fallibility,
+    input: Vec<Intermediate>,
+) -> proc_macro2::TokenStream {
+    let inner = codegen(fallibility, input);
+    let context = template_context(context);
+    let tx = tx();
+    match fallibility {
+        Fallibility::Infallible => quote! {
+            {
+                let (#tx, rx) = ::async_zero_cost_templating::channel();
+                let future = async move {
+                    #inner
+                };
+                ::async_zero_cost_templating::TemplateToStream::<_, _, #context>::new(future, rx)
+            }
+        },
+        Fallibility::Fallible => quote! {
+            {
+                let (#tx, rx) = ::async_zero_cost_templating::channel();
+                let future = async move {
+                    #inner
+                    ::core::result::Result::Ok(())
+                };
+                ::async_zero_cost_templating::TryTemplateToStream::<_, _, _, #context>::new(future, rx)
+            }
+        },
+    }
+}
+
+fn template_context(context: TemplateContext) -> proc_macro2::TokenStream {
+    match context {
+        TemplateContext::Element => quote! { ::async_zero_cost_templating::context::Element },
+        TemplateContext::Attribute => quote! { ::async_zero_cost_templating::context::Attribute },
+        TemplateContext::AttributeValue => {
+            quote! { ::async_zero_cost_templating::context::AttributeValue }
         }
     }
 }
 
-pub fn codegen(input: Vec<Intermediate>) -> proc_macro2::TokenStream {
-    let inner = input.into_iter().map(codegen_intermediate);
+pub fn codegen(fallibility: Fallibility, input: Vec<Intermediate>) -> proc_macro2::TokenStream {
+    let inner = input
+        .into_iter()
+        .map(|input| codegen_intermediate(fallibility, input));
     quote! {
         #(#inner)*
     }
 }
-pub fn codegen_intermediate(input: Intermediate) -> proc_macro2::TokenStream {
+
+fn codegen_if(
+    fallibility: Fallibility,
+    HtmlIf {
+        if_token,
+        cond,
+        then_branch,
+        else_branch,
+    }: HtmlIf<Vec<Intermediate>>,
+) -> proc_macro2::TokenStream {
+    let else_ = else_branch.map(|(else_, else_branch)| match else_branch {
+        HtmlElse::If(html_if) => {
+            let html_if = codegen_if(fallibility, *html_if);
+            quote! {
+                #else_ #html_if
+            }
+        }
+        HtmlElse::Else(_brace, inner) => {
+            let inner = codegen(fallibility, inner);
+            quote! {
+                #else_ {
+                    #inner
+                }
+            }
+        }
+    });
+    let inner = codegen(fallibility, then_branch.1);
+    quote! {
+        #if_token #cond {
+            #inner
+        } #else_
+    }
+}
+
+impl ToTokens for HtmlCondition {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            HtmlCondition::Expr(expr) => expr.to_tokens(tokens),
+            HtmlCondition::Let(let_token, pat, eq_token, expr) => {
+                tokens.extend(quote! { #let_token #pat #eq_token #expr })
+            }
+        }
+    }
+}
+
+pub fn codegen_intermediate(
+    fallibility: Fallibility,
+    input: Intermediate,
+) -> proc_macro2::TokenStream {
     match input {
         Intermediate::Literal(lit, span) => {
+            let tx = tx();
             quote_spanned! {span=>
-                tx.send(::alloc::borrow::Cow::Borrowed(#lit)).await.unwrap();
+                #tx.send(::alloc::borrow::Cow::Borrowed(#lit)).await;
             }
         }
-        Intermediate::ComputedValue((_brace, computed_value)) => {
+        Intermediate::ComputedValue(context, (_brace, computed_value)) => {
             let span = computed_value.span();
+            let escape = match context {
+                EscapeContext::ElementText => quote! { escape_element_text },
+                EscapeContext::AttributeValue => quote! { escape_attribute_value },
+                EscapeContext::Script => quote! { escape_script },
+                EscapeContext::Style => quote! { escape_style },
+                EscapeContext::Comment => quote! { escape_comment },
+            };
+            let tx = tx();
             quote_spanned! {span=>
-                tx.send(#computed_value).await.unwrap();
+                #tx.send(::async_zero_cost_templating::Escape::#escape(#computed_value)).await;
             }
         }
-        Intermediate::Computation((_brace, computation)) => {
-            // TODO if we want to support this for attributes and elements with correct context, create two return types for html! and html_attribute! macros and then verify type here
-            // TODO FIXME this can be used to circumvent escape safety
+        Intermediate::Computation(context, (_brace, computation)) => {
+            // the template has to be for the same context, otherwise e.g. an element could be inserted into an attribute and circumvent escaping
+            let context = template_context(context);
             let span = computation.span();
-            quote_spanned! {span=>
-                let stream: ::async_zero_cost_templating::TemplateToStream<_, _> = #computation;
-                let mut stream = ::std::pin::pin!(stream);
-                while let Some(value) = stream.next().await {
-                    tx.send(value).await.unwrap();
+            let tx = tx();
+            let stream = Ident::new("stream", Span::mixed_site());
+            match fallibility {
+                Fallibility::Infallible => quote_spanned! {span=>
+                    let #stream: ::async_zero_cost_templating::TemplateToStream<_, _, #context> = #computation;
+                    #tx.send_all(#stream).await;
+                },
+                // fallible and infallible templates can be inserted, the error is returned instead of converted so the error type can be inferred
+                Fallibility::Fallible => {
+                    let error = Ident::new("error", Span::mixed_site());
+                    quote_spanned! {span=>
+                        let #stream = ::async_zero_cost_templating::IntoTryTemplate::<#context, _, _>::into_try_template(#computation);
+                        if let ::core::result::Result::Err(#error) = #tx.try_send_all(#stream).await {
+                            return ::core::result::Result::Err(#error);
+                        }
+                    }
                 }
             }
         }
-        Intermediate::If(HtmlIf {
-            if_token,
-            cond,
-            then_branch,
-            else_branch,
-        }) => {
-            let else_ = else_branch.map(|(else_, _brace, inner)| {
-                let inner = codegen(inner);
-                quote! {
-                    #else_ {
-                        #inner
+        Intermediate::Spread((_paren, spread)) => {
+            // the name is validated at runtime, invalid pairs are skipped, and the value is escaped like a computed attribute value
+            let span = spread.span();
+            let tx = tx();
+            let name = Ident::new("name", Span::mixed_site());
+            let value = Ident::new("value", Span::mixed_site());
+            quote_spanned! {span=>
+                for (#name, #value) in #spread {
+                    if let ::core::option::Option::Some(#name) = ::async_zero_cost_templating::attribute_name(#name) {
+                        #tx.send(::alloc::borrow::Cow::Borrowed(" ")).await;
+                        #tx.send(#name).await;
+                        #tx.send(::alloc::borrow::Cow::Borrowed("=\"")).await;
+                        #tx.send(::async_zero_cost_templating::Escape::escape_attribute_value(#value)).await;
+                        #tx.send(::alloc::borrow::Cow::Borrowed("\"")).await;
                     }
                 }
-            });
-            let inner = codegen(then_branch.1);
-            quote! {
-                #if_token #cond {
-                    #inner
-                } #else_
             }
         }
+        Intermediate::If(html_if) => codegen_if(fallibility, html_if),
         Intermediate::For(HtmlForLoop {
             for_token,
+            // lowered to a while loop in `intermediate`
+            await_token: _,
             pat,
             in_token,
             expr,
             body,
         }) => {
-            let inner = codegen(body.1);
+            let inner = codegen(fallibility, body.1);
             quote! {
                 #for_token #pat #in_token #expr {
                     #inner
@@ -89,12 +195,60 @@ pub fn codegen_intermediate(input: Intermediate) -> proc_macro2::TokenStream {
             cond,
             body,
         }) => {
-            let inner = codegen(body.1);
+            let inner = codegen(fallibility, body.1);
             quote! {
                 #while_token #cond {
                     #inner
                 }
             }
         }
+        Intermediate::Match(HtmlMatch {
+            match_token,
+            expr,
+            brace_token: _,
+            arms,
+        }) => {
+            let arms = arms.into_iter().map(
+                |HtmlMatchArm {
+                     pat,
+                     guard,
+                     fat_arrow_token,
+                     body,
+                     comma,
+                 }| {
+                    let guard = guard.map(|(if_token, guard)| quote! { #if_token #guard });
+                    let inner = codegen(fallibility, body.1);
+                    quote! {
+                        #pat #guard #fat_arrow_token {
+                            #inner
+                        } #comma
+                    }
+                },
+            );
+            quote! {
+                #match_token #expr {
+                    #(#arms)*
+                }
+            }
+        }
+        Intermediate::Block(inner) => {
+            let inner = codegen(fallibility, inner);
+            quote! {
+                {
+                    #inner
+                }
+            }
+        }
+        Intermediate::Let(HtmlLet {
+            let_token,
+            pat,
+            eq_token,
+            expr,
+            semi_token,
+        }) => {
+            quote! {
+                #let_token #pat #eq_token #expr #semi_token
+            }
+        }
     }
 }
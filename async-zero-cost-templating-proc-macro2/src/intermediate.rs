@@ -1,38 +1,212 @@
-use itertools::Itertools as _;
-use proc_macro2::{Span, TokenStream};
+use std::convert::Infallible;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2_diagnostics::{Diagnostic, SpanDiagnosticExt};
+use quote::{quote, quote_spanned, ToTokens as _};
 use syn::{
     spanned::Spanned,
     token::{Brace, Paren},
+    Lit, LitStr, Pat, Token,
 };
 
+use crate::entities::NAMED_CHARACTER_REFERENCES;
 use crate::parse::{
-    DashOrColon, HtmlElement, HtmlForLoop, HtmlIf, HtmlInAttributeContext, HtmlInAttributeValueContext, HtmlInElementContext, HtmlWhile
+    name_to_string, HtmlClass, HtmlClassName, HtmlComment, HtmlCondition, HtmlElement, HtmlElse, HtmlForLoop, HtmlIf, HtmlInAttributeContext, HtmlInAttributeValueContext, HtmlInCommentContext, HtmlInElementContext, HtmlLet, HtmlMatch, HtmlMatchArm, HtmlWhile, is_raw, VOID_ELEMENTS
 };
 
-pub enum Intermediate {
+/// Where a computed value is inserted, this decides how it needs to be escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeContext {
+    ElementText,
+    AttributeValue,
+    Script,
+    Style,
+    Comment,
+}
+
+/// Which kind of template a `{ }` has to evaluate to, so e.g. an element can't be inserted as an attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateContext {
+    Element,
+    Attribute,
+    AttributeValue,
+}
+
+/// Whether the template future returns `Result<(), E>` so `?` can be used inside of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallibility {
+    Infallible,
+    Fallible,
+}
+
+/// `Text` is `Unescaped` after lowering and `Infallible` after `escape`, so `simplify` and `codegen` only see literals.
+pub enum Intermediate<Text = Infallible> {
     Literal(String, Span),
-    Computation((Brace, TokenStream)),
-    ComputedValue((Paren, TokenStream)),
-    If(HtmlIf<Vec<Intermediate>>),
-    While(HtmlWhile<Vec<Intermediate>>),
-    For(HtmlForLoop<Vec<Intermediate>>),
+    Text(Text),
+    Computation(TemplateContext, (Brace, TokenStream)),
+    ComputedValue(EscapeContext, (Paren, TokenStream)),
+    Spread((Paren, TokenStream)),
+    If(HtmlIf<Vec<Intermediate<Text>>>),
+    While(HtmlWhile<Vec<Intermediate<Text>>>),
+    For(HtmlForLoop<Vec<Intermediate<Text>>>),
+    Match(HtmlMatch<Vec<Intermediate<Text>>>),
+    Let(HtmlLet),
+    /// Scopes the `let` bindings of an element to that element.
+    Block(Vec<Intermediate<Text>>),
+}
+
+/// Text of a string literal that is escaped for its context at compile time.
+pub struct Unescaped {
+    pub context: EscapeContext,
+    pub text: String,
+    pub span: Span,
+    /// written as `r"..."`
+    pub raw: bool,
+}
+
+impl<Inner> From<HtmlIf<Vec<Inner>>> for HtmlIf<Vec<Intermediate<Unescaped>>>
+where
+    Vec<Intermediate<Unescaped>>: From<Inner>,
+{
+    fn from(value: HtmlIf<Vec<Inner>>) -> Self {
+        HtmlIf {
+            if_token: value.if_token,
+            cond: value.cond,
+            then_branch: (
+                value.then_branch.0,
+                value
+                    .then_branch
+                    .1
+                    .into_iter()
+                    .flat_map(Vec::<Intermediate<Unescaped>>::from)
+                    .collect(),
+            ),
+            else_branch: value.else_branch.map(|(else_token, else_branch)| {
+                (
+                    else_token,
+                    match else_branch {
+                        HtmlElse::If(html_if) => HtmlElse::If(Box::new((*html_if).into())),
+                        HtmlElse::Else(brace, inner) => HtmlElse::Else(
+                            brace,
+                            inner
+                                .into_iter()
+                                .flat_map(Vec::<Intermediate<Unescaped>>::from)
+                                .collect(),
+                        ),
+                    },
+                )
+            }),
+        }
+    }
+}
+
+impl<Inner> From<HtmlMatch<Vec<Inner>>> for Intermediate<Unescaped>
+where
+    Vec<Intermediate<Unescaped>>: From<Inner>,
+{
+    fn from(value: HtmlMatch<Vec<Inner>>) -> Self {
+        Intermediate::Match(HtmlMatch {
+            match_token: value.match_token,
+            expr: value.expr,
+            brace_token: value.brace_token,
+            arms: value
+                .arms
+                .into_iter()
+                .map(|arm| HtmlMatchArm {
+                    pat: arm.pat,
+                    guard: arm.guard,
+                    fat_arrow_token: arm.fat_arrow_token,
+                    body: (
+                        arm.body.0,
+                        arm.body
+                            .1
+                            .into_iter()
+                            .flat_map(Vec::<Intermediate<Unescaped>>::from)
+                            .collect(),
+                    ),
+                    comma: arm.comma,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl<Inner> From<HtmlForLoop<Vec<Inner>>> for Vec<Intermediate<Unescaped>>
+where
+    Vec<Intermediate<Unescaped>>: From<Inner>,
+{
+    fn from(value: HtmlForLoop<Vec<Inner>>) -> Self {
+        for_loop(HtmlForLoop {
+            for_token: value.for_token,
+            await_token: value.await_token,
+            pat: value.pat,
+            in_token: value.in_token,
+            expr: value.expr,
+            body: (
+                value.body.0,
+                value
+                    .body
+                    .1
+                    .into_iter()
+                    .flat_map(Vec::<Intermediate<Unescaped>>::from)
+                    .collect(),
+            ),
+        })
+    }
+}
+
+fn for_loop(
+    HtmlForLoop {
+        for_token,
+        await_token,
+        pat,
+        in_token,
+        expr,
+        body,
+    }: HtmlForLoop<Vec<Intermediate<Unescaped>>>,
+) -> Vec<Intermediate<Unescaped>> {
+    let Some(await_token) = await_token else {
+        return Vec::from([Intermediate::For(HtmlForLoop {
+            for_token,
+            await_token,
+            pat,
+            in_token,
+            expr,
+            body,
+        })]);
+    };
+    // `for await` is a `while let` over the pinned stream, the match scopes the stream to the loop
+    let span = await_token.span;
+    let values = Ident::new("values", Span::mixed_site());
+    Vec::from([Intermediate::Match(HtmlMatch {
+        match_token: Token![match](span),
+        expr: quote_spanned! {expr.span()=> ::core::pin::pin!(#expr) },
+        brace_token: Brace(span),
+        arms: Vec::from([HtmlMatchArm {
+            pat: Pat::Verbatim(quote! { mut #values }),
+            guard: None,
+            fat_arrow_token: Token![=>](span),
+            body: (
+                Brace(span),
+                Vec::from([Intermediate::While(HtmlWhile {
+                    while_token: Token![while](for_token.span),
+                    cond: quote! {
+                        let ::core::option::Option::Some(#pat) = ::async_zero_cost_templating::next(#values.as_mut()).await
+                    },
+                    body,
+                })]),
+            ),
+            comma: None,
+        }]),
+    })])
 }
 
-impl From<HtmlInAttributeContext> for Vec<Intermediate> {
+impl From<HtmlInAttributeContext> for Vec<Intermediate<Unescaped>> {
     fn from(value: HtmlInAttributeContext) -> Self {
         match value {
             HtmlInAttributeContext::Literal(key, value) => Vec::from_iter(
                 [Intermediate::Literal(
-                    " ".to_owned() + &key.pairs()
-                    .map(|p| {
-                        p.value().to_string()
-                            + match p.punct() {
-                                Some(DashOrColon::Colon(_)) => ":",
-                                Some(DashOrColon::Dash(_)) => "-",
-                                None => "",
-                            }
-                    })
-                    .join(""),
+                    " ".to_owned() + &name_to_string(&key),
                     key.first().unwrap().span(),
                 )]
                 .into_iter()
@@ -41,7 +215,12 @@ impl From<HtmlInAttributeContext> for Vec<Intermediate> {
                         .map(|value| {
                             [Intermediate::Literal(r#"=""#.to_owned(), value.0.span())]
                                 .into_iter()
-                                .chain(value.1.into_iter().flat_map(Vec::<Intermediate>::from))
+                                .chain(
+                                    value
+                                        .1
+                                        .into_iter()
+                                        .flat_map(Vec::<Intermediate<Unescaped>>::from),
+                                )
                                 .chain([Intermediate::Literal(r#"""#.to_owned(), value.0.span())])
                         })
                         .into_iter()
@@ -49,55 +228,58 @@ impl From<HtmlInAttributeContext> for Vec<Intermediate> {
                 ),
             ),
             HtmlInAttributeContext::Computation(computation) => {
-                Vec::from([Intermediate::Computation(computation)])
+                Vec::from([Intermediate::Computation(
+                    TemplateContext::Attribute,
+                    computation,
+                )])
             }
-            HtmlInAttributeContext::If(HtmlIf {
-                if_token,
-                cond,
-                then_branch,
-                else_branch,
-            }) => Vec::from([Intermediate::If(HtmlIf {
-                if_token,
-                cond,
-                then_branch: (
-                    then_branch.0,
-                    then_branch
-                        .1
-                        .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
-                        .collect(),
-                ),
-                else_branch: else_branch.map(|else_branch| {
-                    (
-                        else_branch.0,
-                        else_branch.1,
-                        else_branch
-                            .2
-                            .into_iter()
-                            .flat_map(Vec::<Intermediate>::from)
-                            .collect(),
-                    )
-                }),
-            })]),
-            HtmlInAttributeContext::For(HtmlForLoop {
-                for_token,
-                pat,
-                in_token,
-                expr,
-                body,
-            }) => Vec::from([Intermediate::For(HtmlForLoop {
-                for_token,
-                pat,
-                in_token,
-                expr,
-                body: (
-                    body.0,
-                    body.1
-                        .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
-                        .collect(),
-                ),
-            })]),
+            HtmlInAttributeContext::Optional(key, question, _eq, (paren, value)) => {
+                // lowered to a match so the literals and the escaping work like for any other attribute
+                let span = question.span;
+                let name = " ".to_owned() + &name_to_string(&key);
+                let inner = Ident::new("value", Span::mixed_site());
+                let arm = |pat: TokenStream, body: Vec<Intermediate<Unescaped>>| HtmlMatchArm {
+                    pat: Pat::Verbatim(pat),
+                    guard: None,
+                    fat_arrow_token: Token![=>](span),
+                    body: (Brace(span), body),
+                    comma: None,
+                };
+                Vec::from([Intermediate::Match(HtmlMatch {
+                    match_token: Token![match](span),
+                    expr: quote_spanned! {paren.span.join()=>
+                        ::async_zero_cost_templating::OptionalAttribute::into_attribute(#value)
+                    },
+                    brace_token: Brace(span),
+                    arms: Vec::from([
+                        arm(
+                            quote! { ::core::option::Option::Some(::core::option::Option::Some(#inner)) },
+                            Vec::from([
+                                Intermediate::Literal(name.clone() + r#"=""#, span),
+                                Intermediate::ComputedValue(
+                                    EscapeContext::AttributeValue,
+                                    (paren, quote! { #inner }),
+                                ),
+                                Intermediate::Literal(r#"""#.to_owned(), span),
+                            ]),
+                        ),
+                        arm(
+                            quote! { ::core::option::Option::Some(::core::option::Option::None) },
+                            Vec::from([Intermediate::Literal(name, span)]),
+                        ),
+                        arm(quote! { ::core::option::Option::None }, Vec::new()),
+                    ]),
+                })])
+            }
+            HtmlInAttributeContext::Spread(_dot2, spread) => {
+                Vec::from([Intermediate::Spread(spread)])
+            }
+            HtmlInAttributeContext::If(html_if) => Vec::from([Intermediate::If(html_if.into())]),
+            HtmlInAttributeContext::For(html_for) => html_for.into(),
+            HtmlInAttributeContext::Match(html_match) => {
+                Vec::from([Intermediate::from(html_match)])
+            }
+            HtmlInAttributeContext::Let(html_let) => Vec::from([Intermediate::Let(html_let)]),
             crate::parse::HtmlInAttributeContext::While(HtmlWhile {
                 while_token,
                 cond,
@@ -109,7 +291,7 @@ impl From<HtmlInAttributeContext> for Vec<Intermediate> {
                     body.0,
                     body.1
                         .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
+                        .flat_map(Vec::<Intermediate<Unescaped>>::from)
                         .collect(),
                 ),
             })]),
@@ -117,65 +299,50 @@ impl From<HtmlInAttributeContext> for Vec<Intermediate> {
     }
 }
 
-impl From<HtmlInAttributeValueContext> for Vec<Intermediate> {
+impl From<HtmlInAttributeValueContext> for Vec<Intermediate<Unescaped>> {
     fn from(value: HtmlInAttributeValueContext) -> Self {
         match value {
             crate::parse::HtmlInAttributeValueContext::Literal(literal) => {
-                Vec::from([Intermediate::Literal(literal.value(), literal.span())])
+                Vec::from([text(&literal, EscapeContext::AttributeValue)])
             }
             crate::parse::HtmlInAttributeValueContext::ComputedValue(computed_value) => {
-                Vec::from([Intermediate::ComputedValue(computed_value)])
+                Vec::from([Intermediate::ComputedValue(
+                    EscapeContext::AttributeValue,
+                    computed_value,
+                )])
             }
             crate::parse::HtmlInAttributeValueContext::Computation(computation) => {
-                Vec::from([Intermediate::Computation(computation)])
+                Vec::from([Intermediate::Computation(
+                    TemplateContext::AttributeValue,
+                    computation,
+                )])
+            }
+            crate::parse::HtmlInAttributeValueContext::If(html_if) => Vec::from([Intermediate::If(html_if.into())]),
+            crate::parse::HtmlInAttributeValueContext::For(html_for) => html_for.into(),
+            crate::parse::HtmlInAttributeValueContext::Match(html_match) => {
+                Vec::from([Intermediate::from(html_match)])
+            }
+            crate::parse::HtmlInAttributeValueContext::Let(html_let) => Vec::from([Intermediate::Let(html_let)]),
+            crate::parse::HtmlInAttributeValueContext::ClassList(classes) => class_list(classes),
+            crate::parse::HtmlInAttributeValueContext::Unquoted(minus, literal) => {
+                let value = match &literal {
+                    Lit::Int(literal) => literal.base10_digits().to_owned(),
+                    Lit::Float(literal) => literal.base10_digits().to_owned(),
+                    Lit::Char(literal) => literal.value().to_string(),
+                    literal => literal.to_token_stream().to_string(),
+                };
+                let value = if minus.is_some() {
+                    format!("-{value}")
+                } else {
+                    value
+                };
+                Vec::from([Intermediate::Text(Unescaped {
+                    context: EscapeContext::AttributeValue,
+                    text: value,
+                    span: literal.span(),
+                    raw: false,
+                })])
             }
-            crate::parse::HtmlInAttributeValueContext::If(HtmlIf {
-                if_token,
-                cond,
-                then_branch,
-                else_branch,
-            }) => Vec::from([Intermediate::If(HtmlIf {
-                if_token,
-                cond,
-                then_branch: (
-                    then_branch.0,
-                    then_branch
-                        .1
-                        .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
-                        .collect(),
-                ),
-                else_branch: else_branch.map(|else_branch| {
-                    (
-                        else_branch.0,
-                        else_branch.1,
-                        else_branch
-                            .2
-                            .into_iter()
-                            .flat_map(Vec::<Intermediate>::from)
-                            .collect(),
-                    )
-                }),
-            })]),
-            crate::parse::HtmlInAttributeValueContext::For(HtmlForLoop {
-                for_token,
-                pat,
-                in_token,
-                expr,
-                body,
-            }) => Vec::from([Intermediate::For(HtmlForLoop {
-                for_token,
-                pat,
-                in_token,
-                expr,
-                body: (
-                    body.0,
-                    body.1
-                        .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
-                        .collect(),
-                ),
-            })]),
             crate::parse::HtmlInAttributeValueContext::While(HtmlWhile {
                 while_token,
                 cond,
@@ -187,7 +354,7 @@ impl From<HtmlInAttributeValueContext> for Vec<Intermediate> {
                     body.0,
                     body.1
                         .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
+                        .flat_map(Vec::<Intermediate<Unescaped>>::from)
                         .collect(),
                 ),
             })]),
@@ -195,65 +362,281 @@ impl From<HtmlInAttributeValueContext> for Vec<Intermediate> {
     }
 }
 
-impl From<HtmlInElementContext> for Vec<Intermediate> {
+/// What is known at compile time about the classes before the current one.
+#[derive(Clone, Copy)]
+enum ClassListState {
+    Empty,
+    NonEmpty,
+    /// a conditional class may have been emitted, the `separator` variable knows
+    Unknown,
+}
+
+/// With the `minify` feature comments are left out, except for conditional comments like `<!--[if mso]>` that change how
+/// the page is rendered. Computed values are still evaluated so their side effects and bindings don't depend on the feature.
+fn comment_to_intermediate(comment: HtmlComment) -> Vec<Intermediate<Unescaped>> {
+    let conditional = match comment.content.first() {
+        Some(HtmlInCommentContext::Literal(literal)) => {
+            let value = literal.value();
+            value.starts_with("[if") || value.starts_with("<![endif]")
+        }
+        _ => false,
+    };
+    if cfg!(feature = "minify") && !conditional {
+        let values: Vec<_> = comment
+            .content
+            .into_iter()
+            .filter_map(|content| match content {
+                HtmlInCommentContext::Literal(_) => None,
+                HtmlInCommentContext::ComputedValue((paren, value)) => {
+                    let span = paren.span.join();
+                    Some(Intermediate::Let(HtmlLet {
+                        let_token: Token![let](span),
+                        pat: quote! { _ },
+                        eq_token: Token![=](span),
+                        expr: quote_spanned! {span=> &(#value) },
+                        semi_token: Token![;](span),
+                    }))
+                }
+            })
+            .collect();
+        // a block so the element around the comment doesn't become one, see the lowering of elements
+        return if values.is_empty() {
+            Vec::new()
+        } else {
+            Vec::from([Intermediate::Block(values)])
+        };
+    }
+    let mut output = Vec::from([Intermediate::Literal(
+        "<!--".to_owned(),
+        comment.open_start.span,
+    )]);
+    output.extend(comment.content.into_iter().map(|content| match content {
+        HtmlInCommentContext::Literal(literal) => text(&literal, EscapeContext::Comment),
+        HtmlInCommentContext::ComputedValue(computed_value) => {
+            Intermediate::ComputedValue(EscapeContext::Comment, computed_value)
+        }
+    }));
+    output.push(Intermediate::Literal(
+        "-->".to_owned(),
+        comment.close_end.span,
+    ));
+    output
+}
+
+fn text(literal: &LitStr, context: EscapeContext) -> Intermediate<Unescaped> {
+    Intermediate::Text(Unescaped {
+        context,
+        text: literal.value(),
+        span: literal.span(),
+        raw: is_raw(literal),
+    })
+}
+
+/// Escapes text that is known at compile time like the functions in the `escape` module of the runtime crate.
+///
+/// The content of `<script>` and `<style>` is raw text that isn't escaped, `check_raw_text` makes sure it can't end the element.
+/// In raw string literals like `r"&nbsp;"` known character references are kept, everything else is escaped as well.
+fn escape_text(context: EscapeContext, input: &str, raw: bool) -> String {
+    match context {
+        EscapeContext::ElementText | EscapeContext::AttributeValue if raw => {
+            let mut output = String::with_capacity(input.len());
+            let mut rest = input;
+            while let Some(index) = rest.find('&') {
+                output.push_str(&escape_text(context, &rest[..index], false));
+                match character_reference(&rest[index + 1..]) {
+                    Some(CharacterReference {
+                        reference,
+                        known: true,
+                        ..
+                    }) => {
+                        output.push('&');
+                        output.push_str(reference);
+                        output.push(';');
+                        rest = &rest[index + reference.len() + 2..];
+                    }
+                    _ => {
+                        output.push_str("&amp;");
+                        rest = &rest[index + 1..];
+                    }
+                }
+            }
+            output.push_str(&escape_text(context, rest, false));
+            output
+        }
+        EscapeContext::ElementText => input
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        EscapeContext::AttributeValue => input
+            .replace('&', "&amp;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#39;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        EscapeContext::Script | EscapeContext::Style => input.to_owned(),
+        EscapeContext::Comment => {
+            let mut output = String::with_capacity(input.len());
+            if input.starts_with('>') || input.starts_with("->") {
+                output.push(' ');
+            }
+            for character in input.chars() {
+                if character == '-' && (output.ends_with('-') || output.ends_with("<!")) {
+                    output.push(' ');
+                }
+                output.push(character);
+            }
+            if output.ends_with('-') {
+                output.push(' ');
+            }
+            output
+        }
+    }
+}
+
+/// Lowers a class list so that constant classes become literals that `simplify` merges and
+/// only conditional classes need a runtime check whether a separating space is needed.
+///
+/// A computed class is treated like a conditional one that is only present if it isn't empty.
+fn class_list(classes: Vec<HtmlClass>) -> Vec<Intermediate<Unescaped>> {
+    let separator = Ident::new("separator", Span::mixed_site());
+    let mut state = ClassListState::Empty;
+    let mut result = Vec::new();
+    for HtmlClass {
+        class,
+        condition,
+        comma: _,
+    } in classes
+    {
+        let mut conditions: Vec<HtmlCondition> = condition
+            .map(|(_fat_arrow, condition)| HtmlCondition::Expr(condition))
+            .into_iter()
+            .collect();
+        let (class, span) = match class {
+            HtmlClassName::Literal(literal) if literal.value().is_empty() => continue,
+            HtmlClassName::Literal(literal) => (
+                text(&literal, EscapeContext::AttributeValue),
+                literal.span(),
+            ),
+            HtmlClassName::ComputedValue((paren, value)) => {
+                let span = paren.span.join();
+                let escaped = Ident::new("class", Span::mixed_site());
+                conditions.push(HtmlCondition::Let(
+                    Token![let](span),
+                    quote! { ::core::option::Option::Some(#escaped) },
+                    Token![=](span),
+                    quote_spanned! {span=>
+                        ::core::option::Option::Some(::async_zero_cost_templating::Escape::escape_attribute_value(#value))
+                            .filter(|#escaped| !#escaped.is_empty())
+                    },
+                ));
+                (
+                    Intermediate::ComputedValue(
+                        EscapeContext::AttributeValue,
+                        (
+                            paren,
+                            quote! { ::async_zero_cost_templating::PreEscaped(#escaped) },
+                        ),
+                    ),
+                    span,
+                )
+            }
+        };
+        let html_if = |cond: HtmlCondition, body: Vec<Intermediate<Unescaped>>| {
+            Intermediate::If(HtmlIf {
+                if_token: Token![if](span),
+                cond,
+                then_branch: (Brace(span), body),
+                else_branch: None,
+            })
+        };
+        let conditional = !conditions.is_empty();
+        // the conditions are nested in the order they were pushed
+        let html_ifs = |body: Vec<Intermediate<Unescaped>>| {
+            conditions.into_iter().rev().fold(body, |body, condition| {
+                Vec::from([html_if(condition, body)])
+            })
+        };
+        let space = || Intermediate::Literal(" ".to_owned(), span);
+        state = match (conditional, state) {
+            (false, ClassListState::Empty) => {
+                result.push(class);
+                ClassListState::NonEmpty
+            }
+            (false, ClassListState::NonEmpty) => {
+                result.extend([space(), class]);
+                ClassListState::NonEmpty
+            }
+            (false, ClassListState::Unknown) => {
+                result.extend([
+                    html_if(
+                        HtmlCondition::Expr(quote! { #separator }),
+                        Vec::from([space()]),
+                    ),
+                    class,
+                ]);
+                ClassListState::NonEmpty
+            }
+            (true, ClassListState::NonEmpty) => {
+                result.extend(html_ifs(Vec::from([space(), class])));
+                ClassListState::NonEmpty
+            }
+            (true, state) => {
+                if let ClassListState::Empty = state {
+                    result.push(Intermediate::Let(HtmlLet {
+                        let_token: Token![let](span),
+                        pat: quote! { mut #separator },
+                        eq_token: Token![=](span),
+                        expr: quote! { false },
+                        semi_token: Token![;](span),
+                    }));
+                }
+                let separate = html_if(
+                    HtmlCondition::Expr(quote! { ::core::mem::replace(&mut #separator, true) }),
+                    Vec::from([space()]),
+                );
+                result.extend(html_ifs(Vec::from([separate, class])));
+                ClassListState::Unknown
+            }
+        };
+    }
+    // the separator must not make the whole element a block, see the lowering of elements
+    if result
+        .iter()
+        .any(|intermediate| matches!(intermediate, Intermediate::Let(_)))
+    {
+        Vec::from([Intermediate::Block(result)])
+    } else {
+        result
+    }
+}
+
+impl From<HtmlInElementContext> for Vec<Intermediate<Unescaped>> {
     fn from(value: HtmlInElementContext) -> Self {
         match value {
             crate::parse::HtmlInElementContext::Literal(literal) => {
-                Vec::from([Intermediate::Literal(literal.value(), literal.span())])
+                Vec::from([text(&literal, EscapeContext::ElementText)])
+            }
+            crate::parse::HtmlInElementContext::Comment(comment) => {
+                comment_to_intermediate(comment)
             }
             crate::parse::HtmlInElementContext::ComputedValue(computed_value) => {
-                Vec::from([Intermediate::ComputedValue(computed_value)])
+                Vec::from([Intermediate::ComputedValue(
+                    EscapeContext::ElementText,
+                    computed_value,
+                )])
             }
             crate::parse::HtmlInElementContext::Computation(computation) => {
-                Vec::from([Intermediate::Computation(computation)])
+                Vec::from([Intermediate::Computation(
+                    TemplateContext::Element,
+                    computation,
+                )])
             }
-            crate::parse::HtmlInElementContext::If(HtmlIf {
-                if_token,
-                cond,
-                then_branch,
-                else_branch,
-            }) => Vec::from([Intermediate::If(HtmlIf {
-                if_token,
-                cond,
-                then_branch: (
-                    then_branch.0,
-                    then_branch
-                        .1
-                        .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
-                        .collect(),
-                ),
-                else_branch: else_branch.map(|else_branch| {
-                    (
-                        else_branch.0,
-                        else_branch.1,
-                        else_branch
-                            .2
-                            .into_iter()
-                            .flat_map(Vec::<Intermediate>::from)
-                            .collect(),
-                    )
-                }),
-            })]),
-            crate::parse::HtmlInElementContext::For(HtmlForLoop {
-                for_token,
-                pat,
-                in_token,
-                expr,
-                body,
-            }) => Vec::from([Intermediate::For(HtmlForLoop {
-                for_token,
-                pat,
-                in_token,
-                expr,
-                body: (
-                    body.0,
-                    body.1
-                        .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
-                        .collect(),
-                ),
-            })]),
+            crate::parse::HtmlInElementContext::If(html_if) => Vec::from([Intermediate::If(html_if.into())]),
+            crate::parse::HtmlInElementContext::For(html_for) => html_for.into(),
+            crate::parse::HtmlInElementContext::Match(html_match) => {
+                Vec::from([Intermediate::from(html_match)])
+            }
+            crate::parse::HtmlInElementContext::Let(html_let) => Vec::from([Intermediate::Let(html_let)]),
             crate::parse::HtmlInElementContext::While(HtmlWhile {
                 while_token,
                 cond,
@@ -265,7 +648,7 @@ impl From<HtmlInElementContext> for Vec<Intermediate> {
                     body.0,
                     body.1
                         .into_iter()
-                        .flat_map(Vec::<Intermediate>::from)
+                        .flat_map(Vec::<Intermediate<Unescaped>>::from)
                         .collect(),
                 ),
             })]),
@@ -273,23 +656,45 @@ impl From<HtmlInElementContext> for Vec<Intermediate> {
                 open_start,
                 open_tag_name,
                 attributes,
+                self_closing,
                 open_end,
                 children,
-            }) => Vec::from_iter(
+            }) => {
+                // the slash is ignored on void elements and only needed for foreign elements
+                let open_end = match self_closing {
+                    Some(self_closing)
+                        if !VOID_ELEMENTS.contains(&open_tag_name.to_string().as_str()) =>
+                    {
+                        Intermediate::Literal("/>".to_owned(), self_closing.span)
+                    }
+                    _ => Intermediate::Literal(">".to_owned(), open_end.span),
+                };
+                let raw_text_context = match open_tag_name.to_string().as_str() {
+                    "script" => Some(EscapeContext::Script),
+                    "style" => Some(EscapeContext::Style),
+                    _ => None,
+                };
+                let element = Vec::from_iter(
                 [
                     Intermediate::Literal("<".to_owned(), open_start.span),
                     Intermediate::Literal(open_tag_name.to_string(), open_tag_name.span()),
                 ]
                 .into_iter()
-                .chain(attributes.into_iter().flat_map(Vec::<Intermediate>::from))
-                .chain([Intermediate::Literal(">".to_owned(), open_end.span)])
+                .chain(attributes.into_iter().flat_map(Vec::<Intermediate<Unescaped>>::from))
+                .chain([open_end])
                 .chain(
                     children
                         .map(|children| {
-                            children
+                            let mut inner: Vec<Intermediate<Unescaped>> = children
                                 .0
                                 .into_iter()
-                                .flat_map(Vec::<Intermediate>::from)
+                                .flat_map(Vec::<Intermediate<Unescaped>>::from)
+                                .collect();
+                            if let Some(context) = raw_text_context {
+                                set_escape_context(&mut inner, context);
+                            }
+                            inner
+                                .into_iter()
                                 .chain([
                                     Intermediate::Literal("<".to_owned(), children.1.span()),
                                     Intermediate::Literal("/".to_owned(), children.2.span()),
@@ -303,11 +708,252 @@ impl From<HtmlInElementContext> for Vec<Intermediate> {
                         .into_iter()
                         .flatten(),
                 ),
-            ),
+            );
+                // only elements with bindings get a block so the literals of other elements can still be merged
+                if element
+                    .iter()
+                    .any(|intermediate| matches!(intermediate, Intermediate::Let(_)))
+                {
+                    Vec::from([Intermediate::Block(element)])
+                } else {
+                    element
+                }
+            }
+        }
+    }
+}
+
+/// The content of `<script>` and `<style>` is raw text so computed values in there need to be escaped for JavaScript or CSS.
+fn set_escape_context(input: &mut [Intermediate<Unescaped>], context: EscapeContext) {
+    for intermediate in input {
+        match intermediate {
+            Intermediate::ComputedValue(escape_context, _)
+            | Intermediate::Text(Unescaped {
+                context: escape_context,
+                ..
+            }) => *escape_context = context,
+            Intermediate::If(html_if) => set_escape_context_if(html_if, context),
+            Intermediate::While(html_while) => set_escape_context(&mut html_while.body.1, context),
+            Intermediate::For(html_for) => set_escape_context(&mut html_for.body.1, context),
+            Intermediate::Match(html_match) => {
+                for arm in &mut html_match.arms {
+                    set_escape_context(&mut arm.body.1, context);
+                }
+            }
+            Intermediate::Block(inner) => set_escape_context(inner, context),
+            Intermediate::Literal(..)
+            | Intermediate::Computation(..)
+            | Intermediate::Spread(_)
+            | Intermediate::Let(_) => {}
+        }
+    }
+}
+
+fn set_escape_context_if(
+    html_if: &mut HtmlIf<Vec<Intermediate<Unescaped>>>,
+    context: EscapeContext,
+) {
+    set_escape_context(&mut html_if.then_branch.1, context);
+    match &mut html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => set_escape_context_if(html_if, context),
+        Some((_, HtmlElse::Else(_, inner))) => set_escape_context(inner, context),
+        None => {}
+    }
+}
+
+/// Escapes the text of string literals now that the context of `<script>` and `<style>` is known.
+pub fn escape(
+    input: Vec<Intermediate<Unescaped>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Intermediate> {
+    input
+        .into_iter()
+        .map(|intermediate| match intermediate {
+            Intermediate::Literal(literal, span) => Intermediate::Literal(literal, span),
+            Intermediate::Text(text) => {
+                check_character_references(&text, diagnostics);
+                Intermediate::Literal(escape_text(text.context, &text.text, text.raw), text.span)
+            }
+            Intermediate::Computation(context, computation) => {
+                Intermediate::Computation(context, computation)
+            }
+            Intermediate::ComputedValue(context, computed_value) => {
+                Intermediate::ComputedValue(context, computed_value)
+            }
+            Intermediate::Spread(spread) => Intermediate::Spread(spread),
+            Intermediate::If(html_if) => Intermediate::If(escape_if(html_if, diagnostics)),
+            Intermediate::While(HtmlWhile {
+                while_token,
+                cond,
+                body,
+            }) => Intermediate::While(HtmlWhile {
+                while_token,
+                cond,
+                body: (body.0, escape(body.1, diagnostics)),
+            }),
+            Intermediate::For(HtmlForLoop {
+                for_token,
+                await_token,
+                pat,
+                in_token,
+                expr,
+                body,
+            }) => Intermediate::For(HtmlForLoop {
+                for_token,
+                await_token,
+                pat,
+                in_token,
+                expr,
+                body: (body.0, escape(body.1, diagnostics)),
+            }),
+            Intermediate::Match(HtmlMatch {
+                match_token,
+                expr,
+                brace_token,
+                arms,
+            }) => Intermediate::Match(HtmlMatch {
+                match_token,
+                expr,
+                brace_token,
+                arms: arms
+                    .into_iter()
+                    .map(|arm| HtmlMatchArm {
+                        pat: arm.pat,
+                        guard: arm.guard,
+                        fat_arrow_token: arm.fat_arrow_token,
+                        body: (arm.body.0, escape(arm.body.1, diagnostics)),
+                        comma: arm.comma,
+                    })
+                    .collect(),
+            }),
+            Intermediate::Let(html_let) => Intermediate::Let(html_let),
+            Intermediate::Block(inner) => Intermediate::Block(escape(inner, diagnostics)),
+        })
+        .collect()
+}
+
+fn escape_if(
+    html_if: HtmlIf<Vec<Intermediate<Unescaped>>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> HtmlIf<Vec<Intermediate>> {
+    HtmlIf {
+        if_token: html_if.if_token,
+        cond: html_if.cond,
+        then_branch: (
+            html_if.then_branch.0,
+            escape(html_if.then_branch.1, diagnostics),
+        ),
+        else_branch: html_if.else_branch.map(|(else_token, else_branch)| {
+            (
+                else_token,
+                match else_branch {
+                    HtmlElse::If(html_if) => {
+                        HtmlElse::If(Box::new(escape_if(*html_if, diagnostics)))
+                    }
+                    HtmlElse::Else(brace, inner) => {
+                        HtmlElse::Else(brace, escape(inner, diagnostics))
+                    }
+                },
+            )
+        }),
+    }
+}
+
+/// Character references are only allowed in raw string literals, as they would be escaped in other string literals.
+///
+/// The raw text of `<script>` and `<style>` and comments don't have character references, so they aren't checked.
+fn check_character_references(text: &Unescaped, diagnostics: &mut Vec<Diagnostic>) {
+    if !matches!(
+        text.context,
+        EscapeContext::ElementText | EscapeContext::AttributeValue
+    ) {
+        return;
+    }
+    let value = &text.text;
+    for (index, _) in value.match_indices('&') {
+        let Some(CharacterReference {
+            reference,
+            named,
+            numeric,
+            known,
+        }) = character_reference(&value[index + 1..])
+        else {
+            continue;
+        };
+        if text.raw {
+            if named && !known {
+                diagnostics.push(
+                    text.span
+                        .error(format!("unknown named character reference &{reference};"))
+                        .help("a literal & is written as &amp;"),
+                );
+            } else if numeric && !known {
+                diagnostics.push(
+                    text.span
+                        .error(format!("invalid numeric character reference &{reference};"))
+                        .help("numeric character references look like &#169; or &#xA9;, a literal & is written as &amp;"),
+                );
+            }
+        } else if known {
+            diagnostics.push(
+                text.span
+                    .error(format!("&{reference}; would be escaped and shown as text"))
+                    .help(format!(
+                        "use a raw string literal like r\"&{reference};\" to insert a character reference"
+                    )),
+            );
         }
     }
 }
 
+struct CharacterReference<'a> {
+    /// the text between `&` and `;`
+    reference: &'a str,
+    named: bool,
+    /// starts with `#`, even if no valid code point follows
+    numeric: bool,
+    known: bool,
+}
+
+/// Finds the character reference at the start of `input`, which is the text after a `&`.
+fn character_reference(input: &str) -> Option<CharacterReference<'_>> {
+    let end = input.find(';')?;
+    let reference = &input[..end];
+    // browsers replace the null character, surrogates and code points above U+10FFFF
+    let valid_numeric = reference.strip_prefix('#').is_some_and(|number| {
+        let (digits, radix) = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16),
+            None => (number, 10),
+        };
+        !digits.is_empty()
+            && digits.chars().all(|c| c.is_digit(radix))
+            && u32::from_str_radix(digits, radix)
+                .ok()
+                .and_then(char::from_u32)
+                .is_some_and(|c| c != '\0')
+    });
+    let named = !reference.is_empty()
+        && reference.starts_with(|c: char| c.is_ascii_alphabetic())
+        && reference.chars().all(|c| c.is_ascii_alphanumeric());
+    let known =
+        valid_numeric || named && NAMED_CHARACTER_REFERENCES.binary_search(&reference).is_ok();
+    Some(CharacterReference {
+        reference,
+        named,
+        numeric: reference.starts_with('#'),
+        known,
+    })
+}
+
+fn simplify_if(html_if: &mut HtmlIf<Vec<Intermediate>>) {
+    html_if.then_branch.1 = simplify(std::mem::take(&mut html_if.then_branch.1));
+    match &mut html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => simplify_if(html_if),
+        Some((_, HtmlElse::Else(_, inner))) => *inner = simplify(std::mem::take(inner)),
+        None => {}
+    }
+}
+
 pub fn simplify(input: Vec<Intermediate>) -> Vec<Intermediate> {
     let (mut acc, current) =
         input
@@ -340,28 +986,59 @@ pub fn simplify(input: Vec<Intermediate>) -> Vec<Intermediate> {
                     (Some((lit, span)), Intermediate::If(mut html_if)) => (
                         {
                             acc.push(Intermediate::Literal(lit, span));
-                            html_if.then_branch.1 = simplify(html_if.then_branch.1);
-                            if let Some(mut else_) = html_if.else_branch {
-                                else_.2 = simplify(else_.2);
-                                html_if.else_branch = Some(else_);
-                            }
+                            simplify_if(&mut html_if);
                             acc.push(Intermediate::If(html_if));
                             acc
                         },
                         None,
                     ),
-                    (Some((lit, span)), Intermediate::ComputedValue(computed)) => (
+                    (Some((lit, span)), Intermediate::Match(mut html_match)) => (
+                        {
+                            acc.push(Intermediate::Literal(lit, span));
+                            for arm in &mut html_match.arms {
+                                arm.body.1 = simplify(std::mem::take(&mut arm.body.1));
+                            }
+                            acc.push(Intermediate::Match(html_match));
+                            acc
+                        },
+                        None,
+                    ),
+                    (Some((lit, span)), Intermediate::Block(inner)) => (
+                        {
+                            acc.push(Intermediate::Literal(lit, span));
+                            acc.push(Intermediate::Block(simplify(inner)));
+                            acc
+                        },
+                        None,
+                    ),
+                    (Some((lit, span)), Intermediate::Let(html_let)) => (
+                        {
+                            acc.push(Intermediate::Literal(lit, span));
+                            acc.push(Intermediate::Let(html_let));
+                            acc
+                        },
+                        None,
+                    ),
+                    (Some((lit, span)), Intermediate::ComputedValue(context, computed)) => (
+                        {
+                            acc.push(Intermediate::Literal(lit, span));
+                            acc.push(Intermediate::ComputedValue(context, computed));
+                            acc
+                        },
+                        None,
+                    ),
+                    (Some((lit, span)), Intermediate::Computation(context, computation)) => (
                         {
                             acc.push(Intermediate::Literal(lit, span));
-                            acc.push(Intermediate::ComputedValue(computed));
+                            acc.push(Intermediate::Computation(context, computation));
                             acc
                         },
                         None,
                     ),
-                    (Some((lit, span)), Intermediate::Computation(computation)) => (
+                    (Some((lit, span)), Intermediate::Spread(spread)) => (
                         {
                             acc.push(Intermediate::Literal(lit, span));
-                            acc.push(Intermediate::Computation(computation));
+                            acc.push(Intermediate::Spread(spread));
                             acc
                         },
                         None,
@@ -384,26 +1061,53 @@ pub fn simplify(input: Vec<Intermediate>) -> Vec<Intermediate> {
                     ),
                     (None, Intermediate::If(mut html_if)) => (
                         {
-                            html_if.then_branch.1 = simplify(html_if.then_branch.1);
-                            if let Some(mut else_) = html_if.else_branch {
-                                else_.2 = simplify(else_.2);
-                                html_if.else_branch = Some(else_);
-                            }
+                            simplify_if(&mut html_if);
                             acc.push(Intermediate::If(html_if));
                             acc
                         },
                         None,
                     ),
-                    (None, Intermediate::ComputedValue(value)) => (
+                    (None, Intermediate::Match(mut html_match)) => (
+                        {
+                            for arm in &mut html_match.arms {
+                                arm.body.1 = simplify(std::mem::take(&mut arm.body.1));
+                            }
+                            acc.push(Intermediate::Match(html_match));
+                            acc
+                        },
+                        None,
+                    ),
+                    (None, Intermediate::Block(inner)) => (
+                        {
+                            acc.push(Intermediate::Block(simplify(inner)));
+                            acc
+                        },
+                        None,
+                    ),
+                    (None, Intermediate::Let(html_let)) => (
+                        {
+                            acc.push(Intermediate::Let(html_let));
+                            acc
+                        },
+                        None,
+                    ),
+                    (None, Intermediate::ComputedValue(context, value)) => (
+                        {
+                            acc.push(Intermediate::ComputedValue(context, value));
+                            acc
+                        },
+                        None,
+                    ),
+                    (None, Intermediate::Computation(context, value)) => (
                         {
-                            acc.push(Intermediate::ComputedValue(value));
+                            acc.push(Intermediate::Computation(context, value));
                             acc
                         },
                         None,
                     ),
-                    (None, Intermediate::Computation(value)) => (
+                    (None, Intermediate::Spread(spread)) => (
                         {
-                            acc.push(Intermediate::Computation(value));
+                            acc.push(Intermediate::Spread(spread));
                             acc
                         },
                         None,
@@ -1,3 +1,6 @@
 pub mod codegen;
+pub mod entities;
 pub mod intermediate;
 pub mod parse;
+#[cfg(feature = "validation")]
+pub mod validation;
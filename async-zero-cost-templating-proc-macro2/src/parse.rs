@@ -1,13 +1,14 @@
 use quote::quote;
 use std::{
+    collections::BTreeSet,
     convert::identity,
     fmt::{Debug, Display},
 };
 
-use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
 use proc_macro2_diagnostics::{Diagnostic, SpanDiagnosticExt};
 use syn::{
-    braced, bracketed, ext::IdentExt, parenthesized, parse::{Parse, ParseStream}, punctuated::Punctuated, spanned::Spanned, token::{Brace, Bracket, Else, For, If, In, Paren, While}, Ident, LitStr, Token
+    braced, bracketed, ext::IdentExt, parenthesized, parse::{Parse, ParseStream}, punctuated::Punctuated, spanned::Spanned, token::{Await, Brace, Bracket, Comma, Else, FatArrow, For, If, In, Match, Paren, While}, Ident, Lit, LitChar, LitFloat, LitInt, LitStr, Pat, Token
 };
 use tracing::instrument;
 use tracing::{error, level_filters::LevelFilter};
@@ -17,11 +18,53 @@ use tracing_subscriber::{
 
 use crate::{
     codegen::top_level,
-    intermediate::{simplify, Intermediate},
+    intermediate::{escape, simplify, Fallibility, Intermediate, TemplateContext, Unescaped},
 };
 
 #[instrument(ret)]
-pub fn top_level_parse(input: TokenStream) -> TokenStream {
+pub fn top_level_parse(input: TokenStream, fallibility: Fallibility) -> TokenStream {
+    template_parse::<HtmlInElementContext>(
+        input,
+        TemplateContext::Element,
+        fallibility,
+        check_namespaces,
+    )
+}
+
+#[instrument(ret)]
+pub fn top_level_parse_attributes(input: TokenStream, fallibility: Fallibility) -> TokenStream {
+    template_parse::<HtmlInAttributeContext>(
+        input,
+        TemplateContext::Attribute,
+        fallibility,
+        |_, _| {},
+    )
+}
+
+#[instrument(ret)]
+pub fn top_level_parse_attribute_value(
+    input: TokenStream,
+    fallibility: Fallibility,
+) -> TokenStream {
+    template_parse::<HtmlInAttributeValueContext>(
+        input,
+        TemplateContext::AttributeValue,
+        fallibility,
+        |_, _| {},
+    )
+}
+
+fn template_parse<T: Debug>(
+    input: TokenStream,
+    context: TemplateContext,
+    fallibility: Fallibility,
+    // checks that need to know the whole template
+    check: fn(&[T], &mut Vec<Diagnostic>),
+) -> TokenStream
+where
+    for<'a> ParseStream<'a>: MyParse<Vec<T>>,
+    Vec<Intermediate<Unescaped>>: From<T>,
+{
     let _ = tracing_subscriber::registry()
         .with(LevelFilter::OFF)
         .with(
@@ -33,23 +76,25 @@ pub fn top_level_parse(input: TokenStream) -> TokenStream {
 
     // this parse will only fail if we didn't fully consume the input
     // if this crashes then you probably didn't directly consume these but just extracted them which doesn't work
-    let html_top_level: MyParseToParse<Vec<HtmlInElementContext>> = match syn::parse2(input) {
+    let mut html_top_level: MyParseToParse<Vec<T>> = match syn::parse2(input) {
         Ok(ok) => ok,
         Err(err) => return Diagnostic::from(err).error("this is a syn internal error, likely some nested method did not consume this token?").emit_as_expr_tokens(),
     };
-    let diagnostics = html_top_level
-        .diagnostics
-        .into_iter()
-        .map(|diagnostic| diagnostic.emit_as_expr_tokens());
+    check(&html_top_level.value, &mut html_top_level.diagnostics);
 
     let intermediate = html_top_level
         .value
         .into_iter()
-        .flat_map(Vec::<Intermediate>::from)
+        .flat_map(Vec::<Intermediate<Unescaped>>::from)
         .collect();
-    let intermediate = simplify(intermediate);
+    let intermediate = simplify(escape(intermediate, &mut html_top_level.diagnostics));
 
-    let output = top_level(intermediate);
+    let diagnostics = html_top_level
+        .diagnostics
+        .into_iter()
+        .map(|diagnostic| diagnostic.emit_as_expr_tokens());
+
+    let output = top_level(context, fallibility, intermediate);
     let output = quote! {
         {
             #(#diagnostics)*
@@ -114,10 +159,27 @@ my_parse!(Token![/]);
 my_parse!(Token![>]);
 my_parse!(Token![!]);
 my_parse!(Option<Token![!]>);
+my_parse!(Option<Token![/]>);
+my_parse!(Option<Token![-]>);
+my_parse!(Token![-]);
+my_parse!(Lit);
 my_parse!(Token![=]);
 my_parse!(Token![in]);
 my_parse!(Token![for]);
 my_parse!(Token![while]);
+my_parse!(Token![let]);
+my_parse!(Token![;]);
+my_parse!(Token![match]);
+my_parse!(Token![=>]);
+my_parse!(Option<Token![,]>);
+my_parse!(Token![..]);
+my_parse!(Token![?]);
+my_parse!(Option<Token![await]>);
+
+/// Whether the literal is written as `r"..."`, character references in those are kept instead of escaped.
+pub fn is_raw(literal: &LitStr) -> bool {
+    literal.token().to_string().starts_with('r')
+}
 
 impl MyParse<Ident> for ParseStream<'_> {
     fn inner_my_parse(self) -> Result<(Ident, Vec<Diagnostic>), Vec<Diagnostic>>
@@ -148,11 +210,13 @@ pub struct MyParseToParse<T> {
     pub diagnostics: Vec<Diagnostic>,
 }
 
-impl Parse for MyParseToParse<Vec<HtmlInElementContext>> {
+impl<T: Debug> Parse for MyParseToParse<T>
+where
+    for<'a> ParseStream<'a>: MyParse<T>,
+{
     #[instrument(err(Debug), ret, name = "HtmlTopLevel")]
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let result =
-            MyParse::<Vec<HtmlInElementContext>>::my_parse(input, identity, identity, Vec::new());
+        let result = MyParse::<T>::my_parse(input, identity, identity, Vec::new());
         match result {
             Ok(ok) => Ok(MyParseToParse {
                 value: ok.0,
@@ -178,7 +242,10 @@ pub enum HtmlInElementContext {
     If(HtmlIf<Vec<HtmlInElementContext>>),
     For(HtmlForLoop<Vec<HtmlInElementContext>>),
     While(HtmlWhile<Vec<HtmlInElementContext>>),
+    Match(HtmlMatch<Vec<HtmlInElementContext>>),
+    Let(HtmlLet),
     Element(HtmlElement),
+    Comment(HtmlComment),
 }
 
 #[derive(Debug)]
@@ -189,6 +256,25 @@ pub enum HtmlInAttributeValueContext {
     If(HtmlIf<Vec<HtmlInAttributeValueContext>>),
     While(HtmlWhile<Vec<HtmlInAttributeValueContext>>),
     For(HtmlForLoop<Vec<HtmlInAttributeValueContext>>),
+    Match(HtmlMatch<Vec<HtmlInAttributeValueContext>>),
+    Let(HtmlLet),
+    /// `width=100`, `step=-0.5` or `accesskey='s'`, the value is quoted in the output.
+    Unquoted(Option<Token![-]>, Lit),
+    /// `["btn", "active" => is_active, (extra)]` joins the present classes with single spaces.
+    ClassList(Vec<HtmlClass>),
+}
+
+#[derive(Debug)]
+pub struct HtmlClass {
+    pub class: HtmlClassName,
+    pub condition: Option<(FatArrow, TokenStream)>,
+    pub comma: Option<Comma>,
+}
+
+#[derive(Debug)]
+pub enum HtmlClassName {
+    Literal(LitStr),
+    ComputedValue((Paren, TokenStream)),
 }
 
 
@@ -202,17 +288,49 @@ pub enum DashOrColon {
 pub enum HtmlInAttributeContext {
     Literal(Punctuated<Ident, DashOrColon>, Option<(Token![=], Vec<HtmlInAttributeValueContext>)>),
     Computation((Brace, TokenStream)),
+    /// `name?=(value)` omits the attribute for `false` or `None` and emits it without a value for `true`.
+    Optional(
+        Punctuated<Ident, DashOrColon>,
+        Token![?],
+        Token![=],
+        (Paren, TokenStream),
+    ),
+    /// `..(attributes)` inserts all `(name, value)` pairs of an iterator.
+    Spread(Token![..], (Paren, TokenStream)),
     If(HtmlIf<Vec<HtmlInAttributeContext>>),
     While(HtmlWhile<Vec<HtmlInAttributeContext>>),
     For(HtmlForLoop<Vec<HtmlInAttributeContext>>),
+    Match(HtmlMatch<Vec<HtmlInAttributeContext>>),
+    Let(HtmlLet),
 }
 
 #[derive(Debug)]
 pub struct HtmlIf<Inner> {
     pub if_token: If,
-    pub cond: TokenStream,
+    pub cond: HtmlCondition,
     pub then_branch: (Brace, Inner),
-    pub else_branch: Option<(Else, Brace, Inner)>,
+    pub else_branch: Option<(Else, HtmlElse<Inner>)>,
+}
+
+#[derive(Debug)]
+pub struct HtmlLet {
+    pub let_token: Token![let],
+    pub pat: TokenStream,
+    pub eq_token: Token![=],
+    pub expr: TokenStream,
+    pub semi_token: Token![;],
+}
+
+#[derive(Debug)]
+pub enum HtmlElse<Inner> {
+    If(Box<HtmlIf<Inner>>),
+    Else(Brace, Inner),
+}
+
+#[derive(Debug)]
+pub enum HtmlCondition {
+    Expr(TokenStream),
+    Let(Token![let], TokenStream, Token![=], TokenStream),
 }
 
 #[derive(Debug)]
@@ -225,16 +343,52 @@ pub struct HtmlWhile<Inner> {
 #[derive(Debug)]
 pub struct HtmlForLoop<Inner> {
     pub for_token: For,
+    /// `for await` iterates over a `Stream` instead of an `Iterator`
+    pub await_token: Option<Await>,
     pub pat: TokenStream,
     pub in_token: In,
     pub expr: TokenStream,
     pub body: (Brace, Inner),
 }
 
+#[derive(Debug)]
+pub struct HtmlMatch<Inner> {
+    pub match_token: Match,
+    pub expr: TokenStream,
+    pub brace_token: Brace,
+    pub arms: Vec<HtmlMatchArm<Inner>>,
+}
+
+#[derive(Debug)]
+pub struct HtmlMatchArm<Inner> {
+    pub pat: Pat,
+    pub guard: Option<(If, TokenStream)>,
+    pub fat_arrow_token: FatArrow,
+    pub body: (Brace, Inner),
+    pub comma: Option<Comma>,
+}
+
 #[derive(Debug)]
 pub struct HtmlTag {
     pub exclamation: Option<Token![!]>,
-    pub name: Ident,
+    pub name: Punctuated<Ident, DashOrColon>,
+}
+
+/// `<!-- "text" (value) -->`
+#[derive(Debug)]
+pub struct HtmlComment {
+    pub open_start: Token![<],
+    pub exclamation: Token![!],
+    pub open_dashes: (Token![-], Token![-]),
+    pub content: Vec<HtmlInCommentContext>,
+    pub close_dashes: (Token![-], Token![-]),
+    pub close_end: Token![>],
+}
+
+#[derive(Debug)]
+pub enum HtmlInCommentContext {
+    Literal(LitStr),
+    ComputedValue((Paren, TokenStream)),
 }
 
 #[derive(Debug)]
@@ -242,6 +396,8 @@ pub struct HtmlElement {
     pub open_start: Token![<],
     pub open_tag_name: HtmlTag,
     pub attributes: Vec<HtmlInAttributeContext>,
+    /// `/>` closes the element without children.
+    pub self_closing: Option<Token![/]>,
     pub open_end: Token![>],
     pub children: Option<(
         Vec<HtmlInElementContext>,
@@ -286,6 +442,20 @@ impl MyParse<HtmlInElementContext> for ParseStream<'_> {
                 |diagnostic| diagnostic.span_note(span, "while parsing while"),
                 diagnostics,
             )?)
+        } else if lookahead.peek(Token![match]) {
+            Ok(MyParse::<HtmlMatch<Vec<HtmlInElementContext>>>::my_parse(
+                self,
+                HtmlInElementContext::Match,
+                |diagnostic| diagnostic.span_note(span, "while parsing match"),
+                diagnostics,
+            )?)
+        } else if lookahead.peek(Token![let]) {
+            Ok(MyParse::<HtmlLet>::my_parse(
+                self,
+                HtmlInElementContext::Let,
+                |diagnostic| diagnostic.span_note(span, "while parsing let"),
+                diagnostics,
+            )?)
         } else if lookahead.peek(Brace) {
             let then_span = self.cursor().token_stream().span();
             if let Ok((brace, content)) = (|| {
@@ -317,12 +487,21 @@ impl MyParse<HtmlInElementContext> for ParseStream<'_> {
                 return Err(diagnostics);
             }
         } else if lookahead.peek(Token![<]) {
-            Ok(MyParse::<HtmlElement>::my_parse(
-                self,
-                HtmlInElementContext::Element,
-                |diagnostics| diagnostics.span_note(span, "while parsing element"),
-                diagnostics,
-            )?)
+            if self.peek2(Token![!]) && self.peek3(Token![-]) {
+                Ok(MyParse::<HtmlComment>::my_parse(
+                    self,
+                    HtmlInElementContext::Comment,
+                    |diagnostics| diagnostics.span_note(span, "while parsing comment"),
+                    diagnostics,
+                )?)
+            } else {
+                Ok(MyParse::<HtmlElement>::my_parse(
+                    self,
+                    HtmlInElementContext::Element,
+                    |diagnostics| diagnostics.span_note(span, "while parsing element"),
+                    diagnostics,
+                )?)
+            }
         } else {
             self.step(|cursor| {
                 if let Some((_, next)) = cursor.token_tree() {
@@ -379,6 +558,22 @@ impl MyParse<HtmlInAttributeValueContext> for ParseStream<'_> {
                     diagnostics,
                 )?,
             )
+        } else if lookahead.peek(Token![match]) {
+            Ok(
+                MyParse::<HtmlMatch<Vec<HtmlInAttributeValueContext>>>::my_parse(
+                    self,
+                    HtmlInAttributeValueContext::Match,
+                    |diagnostic| diagnostic.span_note(span, "while parsing match"),
+                    diagnostics,
+                )?,
+            )
+        } else if lookahead.peek(Token![let]) {
+            Ok(MyParse::<HtmlLet>::my_parse(
+                self,
+                HtmlInAttributeValueContext::Let,
+                |diagnostic| diagnostic.span_note(span, "while parsing let"),
+                diagnostics,
+            )?)
         } else if lookahead.peek(Brace) {
             let then_span = self.cursor().token_stream().span();
             if let Ok((brace, content)) = (|| {
@@ -471,14 +666,14 @@ impl MyParse<HtmlInAttributeContext> for ParseStream<'_> {
         let mut diagnostics = Vec::new();
         let lookahead = self.lookahead1();
         let span = self.cursor().token_stream().span();
-        if lookahead.peek(Token![if]) && !self.peek2(Token![=]) {
+        if lookahead.peek(Token![if]) && !self.peek2(Token![=]) && !self.peek2(Token![?]) {
             Ok(MyParse::<HtmlIf<Vec<HtmlInAttributeContext>>>::my_parse(
                 self,
                 HtmlInAttributeContext::If,
                 |diagnostic| diagnostic.span_note(span, "while parsing if"),
                 diagnostics,
             )?)
-        } else if lookahead.peek(Token![for]) && !self.peek2(Token![=]) {
+        } else if lookahead.peek(Token![for]) && !self.peek2(Token![=]) && !self.peek2(Token![?]) {
             Ok(
                 MyParse::<HtmlForLoop<Vec<HtmlInAttributeContext>>>::my_parse(
                     self,
@@ -487,70 +682,165 @@ impl MyParse<HtmlInAttributeContext> for ParseStream<'_> {
                     diagnostics,
                 )?,
             )
-        } else if lookahead.peek(Token![while]) && !self.peek2(Token![=]) {
+        } else if lookahead.peek(Token![while]) && !self.peek2(Token![=]) && !self.peek2(Token![?])
+        {
             Ok(MyParse::<HtmlWhile<Vec<HtmlInAttributeContext>>>::my_parse(
                 self,
                 HtmlInAttributeContext::While,
                 |diagnostic| diagnostic.span_note(span, "while parsing while"),
                 diagnostics,
             )?)
+        } else if lookahead.peek(Token![match]) && !self.peek2(Token![=]) && !self.peek2(Token![?])
+        {
+            Ok(MyParse::<HtmlMatch<Vec<HtmlInAttributeContext>>>::my_parse(
+                self,
+                HtmlInAttributeContext::Match,
+                |diagnostic| diagnostic.span_note(span, "while parsing match"),
+                diagnostics,
+            )?)
+        } else if lookahead.peek(Token![let]) && !self.peek2(Token![=]) && !self.peek2(Token![?]) {
+            Ok(MyParse::<HtmlLet>::my_parse(
+                self,
+                HtmlInAttributeContext::Let,
+                |diagnostic| diagnostic.span_note(span, "while parsing let"),
+                diagnostics,
+            )?)
         } else if lookahead.peek(Ident::peek_any) {
-            // here
+            let name: Punctuated<Ident, DashOrColon>;
+            (name, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            if self.peek(Token![?]) {
+                let question: Token![?];
+                (question, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+                let eq: Token![=];
+                (eq, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+                let then_span = self.cursor().token_stream().span();
+                return if let Ok((paren, content)) = (|| {
+                    let content;
+                    Ok((parenthesized!(content in self), content))
+                })() {
+                    // TODO FIXME check fully parsed
+                    Ok((
+                        HtmlInAttributeContext::Optional(
+                            name,
+                            question,
+                            eq,
+                            (paren, content.parse().unwrap()),
+                        ),
+                        diagnostics,
+                    ))
+                } else {
+                    diagnostics.push(
+                        then_span
+                            .error("expected ( )")
+                            .help("optional attributes are written as name?=(value)"),
+                    );
+                    Err(diagnostics)
+                };
+            }
             Ok((
-                HtmlInAttributeContext::Literal(
-                    {
-                        let value;
-                        (value, diagnostics) =
+                HtmlInAttributeContext::Literal(name, {
+                    if self.peek(Token![=]) {
+                        let eq: Token![=];
+                        (eq, diagnostics) =
                             MyParse::my_parse(self, identity, identity, diagnostics)?;
-                        value
-                    },
-                    {
-                        if self.peek(Token![=]) {
-                            // TODO FIXME check for string or []
-                            let eq: Token![=];
-                            (eq, diagnostics) =
+                        let lookahead1 = self.lookahead1();
+
+                        let value;
+                        (value, diagnostics) = if self.peek(Token![-])
+                            && (self.peek2(LitInt) || self.peek2(LitFloat))
+                            || lookahead1.peek(LitInt)
+                            || lookahead1.peek(LitFloat)
+                            || lookahead1.peek(LitChar)
+                        {
+                            let minus;
+                            (minus, diagnostics) =
                                 MyParse::my_parse(self, identity, identity, diagnostics)?;
-                            let lookahead1 = self.lookahead1();
-
-                            let value;
-                            (value, diagnostics) = if lookahead1.peek(LitStr) {
-                                MyParse::<LitStr>::my_parse(
-                                    self,
-                                    |value| {
-                                        Vec::from([HtmlInAttributeValueContext::Literal(value)])
-                                    },
-                                    identity,
-                                    diagnostics,
-                                )?
-                            } else if lookahead1.peek(Bracket) {
-                                let then_span = self.cursor().token_stream().span();
-                                if let Ok((_bracket, content)) = (|| {
-                                    let content;
-                                    Ok((bracketed!(content in self), content))
-                                })(
-                                ) {
+                            MyParse::<Lit>::my_parse(
+                                self,
+                                |value| {
+                                    Vec::from([HtmlInAttributeValueContext::Unquoted(minus, value)])
+                                },
+                                identity,
+                                diagnostics,
+                            )?
+                        } else if lookahead1.peek(LitStr) {
+                            MyParse::<LitStr>::my_parse(
+                                self,
+                                |value| Vec::from([HtmlInAttributeValueContext::Literal(value)]),
+                                identity,
+                                diagnostics,
+                            )?
+                        } else if lookahead1.peek(Bracket) {
+                            let then_span = self.cursor().token_stream().span();
+                            if let Ok((_bracket, content)) = (|| {
+                                let content;
+                                Ok((bracketed!(content in self), content))
+                            })() {
+                                if is_class_list(&content) {
+                                    MyParse::<Vec<HtmlClass>>::my_parse(
+                                        &content,
+                                        |classes| {
+                                            Vec::from([HtmlInAttributeValueContext::ClassList(
+                                                classes,
+                                            )])
+                                        },
+                                        identity,
+                                        diagnostics,
+                                    )?
+                                } else {
                                     MyParse::<Vec<HtmlInAttributeValueContext>>::my_parse(
                                         &content,
                                         identity,
                                         identity,
                                         diagnostics,
                                     )?
-                                } else {
-                                    diagnostics.push(then_span.error("expected { }"));
-                                    return Err(diagnostics);
                                 }
                             } else {
-                                diagnostics.push(Diagnostic::from(lookahead1.error()));
+                                diagnostics.push(then_span.error("expected { }"));
                                 return Err(diagnostics);
-                            };
-                            Some((eq, value))
+                            }
+                        } else if lookahead1.peek(Paren) || lookahead1.peek(Brace) {
+                            MyParse::<HtmlInAttributeValueContext>::my_parse(
+                                self,
+                                |value| Vec::from([value]),
+                                identity,
+                                diagnostics,
+                            )?
                         } else {
-                            None
-                        }
-                    },
-                ),
+                            // `-` is peeked without the lookahead, so the message is written out here
+                            diagnostics.push(Diagnostic::from(self.error(
+                                "expected one of: string literal, integer literal, floating point literal, `-` followed by a number, character literal, parentheses, curly braces, square brackets",
+                            )));
+                            return Err(diagnostics);
+                        };
+                        Some((eq, value))
+                    } else {
+                        None
+                    }
+                }),
                 diagnostics,
             ))
+        } else if lookahead.peek(Token![..]) {
+            let dot2: Token![..];
+            (dot2, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            let then_span = self.cursor().token_stream().span();
+            if let Ok((paren, content)) = (|| {
+                let content;
+                Ok((parenthesized!(content in self), content))
+            })() {
+                // TODO FIXME check fully parsed
+                Ok((
+                    HtmlInAttributeContext::Spread(dot2, (paren, content.parse().unwrap())),
+                    diagnostics,
+                ))
+            } else {
+                diagnostics.push(
+                    then_span
+                        .error("expected ( )")
+                        .help("spread attributes are written as ..(attributes)"),
+                );
+                Err(diagnostics)
+            }
         } else if lookahead.peek(Brace) {
             let then_span = self.cursor().token_stream().span();
             if let Ok((brace, content)) = (|| {
@@ -596,31 +886,10 @@ where
                     result
                 },
                 cond: {
-                    let result = self.step(|cursor| {
-                        let mut rest = *cursor;
-                        let mut tokens = TokenStream::new();
-                        while let Some((tt, next)) = rest.token_tree() {
-                            match &tt {
-                                TokenTree::Group(group)
-                                    if group.delimiter() == Delimiter::Brace =>
-                                {
-                                    return Ok((tokens, rest));
-                                }
-                                _ => {
-                                    tokens.extend(std::iter::once(rest.token_tree().unwrap().0));
-                                    rest = next;
-                                }
-                            }
-                        }
-                        Err(cursor.error("no { was found after this point"))
-                    });
-                    match result {
-                        Ok(value) => value,
-                        Err(error) => {
-                            diagnostics.push(error.into());
-                            return Err(diagnostics);
-                        }
-                    }
+                    let result;
+                    (result, diagnostics) =
+                        MyParse::<HtmlCondition>::my_parse(self, identity, identity, diagnostics)?;
+                    result
                 },
                 then_branch: {
                     let then_span = self.cursor().token_stream().span();
@@ -655,7 +924,18 @@ where
                             diagnostics,
                         )?;
 
-                        if let Ok((brace, content)) = (|| {
+                        if self.peek(Token![if]) {
+                            let result;
+                            (result, diagnostics) = MyParse::<HtmlIf<Inner>>::my_parse(
+                                self,
+                                identity,
+                                |diagnostic| {
+                                    diagnostic.span_note(else_span, "while parsing else if branch")
+                                },
+                                diagnostics,
+                            )?;
+                            Some((else_, HtmlElse::If(Box::new(result))))
+                        } else if let Ok((brace, content)) = (|| {
                             let content;
                             Ok((braced!(content in self), content))
                         })() {
@@ -669,9 +949,9 @@ where
                                 },
                                 diagnostics,
                             )?;
-                            Some((else_, brace, result))
+                            Some((else_, HtmlElse::Else(brace, result)))
                         } else {
-                            diagnostics.push(else_span.error("expected { }"));
+                            diagnostics.push(else_span.error("expected { } or if"));
                             return Err(diagnostics);
                         }
                     } else {
@@ -684,6 +964,147 @@ where
     }
 }
 
+/// The pattern of a `let` ends at the first `=` that is not part of `==`, `=>`, `<=` or a `..=` range.
+fn pattern_until_eq(input: ParseStream) -> syn::Result<TokenStream> {
+    input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = TokenStream::new();
+        let mut previous_joint = false;
+        while let Some((tt, next)) = rest.token_tree() {
+            match &tt {
+                TokenTree::Punct(punct)
+                    if punct.as_char() == '='
+                        && punct.spacing() == Spacing::Alone
+                        && !previous_joint =>
+                {
+                    return Ok((tokens, rest));
+                }
+                _ => {
+                    previous_joint = matches!(&tt, TokenTree::Punct(punct) if punct.spacing() == Spacing::Joint);
+                    tokens.extend(std::iter::once(tt));
+                    rest = next;
+                }
+            }
+        }
+        Err(cursor.error("no = was found after this pattern"))
+    })
+}
+
+impl MyParse<HtmlLet> for ParseStream<'_> {
+    #[instrument(err(Debug), ret, name = "HtmlLet")]
+    fn inner_my_parse(self) -> Result<(HtmlLet, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let let_token: Token![let];
+        (let_token, diagnostics) =
+            MyParse::<Token![let]>::my_parse(self, identity, identity, diagnostics)?;
+        let pat = match pattern_until_eq(self) {
+            Ok(value) => value,
+            Err(error) => {
+                diagnostics.push(error.into());
+                return Err(diagnostics);
+            }
+        };
+        let eq_token: Token![=];
+        (eq_token, diagnostics) =
+            MyParse::<Token![=]>::my_parse(self, identity, identity, diagnostics)?;
+        let result = self.step(|cursor| {
+            let mut rest = *cursor;
+            let mut tokens = TokenStream::new();
+            while let Some((tt, next)) = rest.token_tree() {
+                match &tt {
+                    TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                        return Ok((tokens, rest));
+                    }
+                    _ => {
+                        tokens.extend(std::iter::once(tt));
+                        rest = next;
+                    }
+                }
+            }
+            Err(cursor.error("no ; was found after this point"))
+        });
+        let expr = match result {
+            Ok(value) => value,
+            Err(error) => {
+                diagnostics.push(error.into());
+                return Err(diagnostics);
+            }
+        };
+        let semi_token: Token![;];
+        (semi_token, diagnostics) =
+            MyParse::<Token![;]>::my_parse(self, identity, identity, diagnostics)?;
+        Ok((
+            HtmlLet {
+                let_token,
+                pat,
+                eq_token,
+                expr,
+                semi_token,
+            },
+            diagnostics,
+        ))
+    }
+}
+
+impl MyParse<HtmlCondition> for ParseStream<'_> {
+    #[instrument(err(Debug), ret, name = "HtmlCondition")]
+    fn inner_my_parse(self) -> Result<(HtmlCondition, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let until_brace = || {
+            self.step(|cursor| {
+                let mut rest = *cursor;
+                let mut tokens = TokenStream::new();
+                while let Some((tt, next)) = rest.token_tree() {
+                    match &tt {
+                        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                            return Ok((tokens, rest));
+                        }
+                        _ => {
+                            tokens.extend(std::iter::once(tt));
+                            rest = next;
+                        }
+                    }
+                }
+                Err(cursor.error("no { was found after this point"))
+            })
+        };
+        if self.peek(Token![let]) {
+            let let_token: Token![let];
+            (let_token, diagnostics) =
+                MyParse::<Token![let]>::my_parse(self, identity, identity, diagnostics)?;
+            let result = pattern_until_eq(self);
+            let pat = match result {
+                Ok(value) => value,
+                Err(error) => {
+                    diagnostics.push(error.into());
+                    return Err(diagnostics);
+                }
+            };
+            let eq_token: Token![=];
+            (eq_token, diagnostics) =
+                MyParse::<Token![=]>::my_parse(self, identity, identity, diagnostics)?;
+            match until_brace() {
+                Ok(expr) => Ok((
+                    HtmlCondition::Let(let_token, pat, eq_token, expr),
+                    diagnostics,
+                )),
+                Err(error) => {
+                    diagnostics.push(error.into());
+                    Err(diagnostics)
+                }
+            }
+        } else {
+            match until_brace() {
+                Ok(expr) => Ok((HtmlCondition::Expr(expr), diagnostics)),
+                Err(error) => {
+                    diagnostics.push(error.into());
+                    Err(diagnostics)
+                }
+            }
+        }
+    }
+}
+
 impl<Inner: Debug> MyParse<HtmlForLoop<Inner>> for ParseStream<'_>
 where
     for<'a> ParseStream<'a>: MyParse<Inner>,
@@ -694,6 +1115,9 @@ where
         let for_token: Token![for];
         (for_token, diagnostics) =
             MyParse::<Token![for]>::my_parse(self, identity, identity, diagnostics)?;
+        let await_token;
+        (await_token, diagnostics) =
+            MyParse::<Option<Token![await]>>::my_parse(self, identity, identity, diagnostics)?;
 
         let result = self.step(|cursor| {
             let mut rest = *cursor;
@@ -763,6 +1187,7 @@ where
             Ok((
                 HtmlForLoop {
                     for_token,
+                    await_token,
                     pat,
                     in_token,
                     expr,
@@ -840,6 +1265,165 @@ where
     }
 }
 
+impl<Inner: Debug> MyParse<HtmlMatch<Inner>> for ParseStream<'_>
+where
+    for<'a> ParseStream<'a>: MyParse<Inner>,
+{
+    #[instrument(err(Debug), ret, name = "HtmlMatch<Inner>")]
+    fn inner_my_parse(self) -> Result<(HtmlMatch<Inner>, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let match_token: Token![match];
+        (match_token, diagnostics) =
+            MyParse::<Token![match]>::my_parse(self, identity, identity, diagnostics)?;
+
+        let result = self.step(|cursor| {
+            let mut rest = *cursor;
+            let mut tokens = TokenStream::new();
+            while let Some((tt, next)) = rest.token_tree() {
+                match &tt {
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                        return Ok((tokens, rest));
+                    }
+                    _ => {
+                        tokens.extend(std::iter::once(rest.token_tree().unwrap().0));
+                        rest = next;
+                    }
+                }
+            }
+            Err(cursor.error("no { was found after this point"))
+        });
+        let expr = match result {
+            Ok(value) => value,
+            Err(error) => {
+                diagnostics.push(error.into());
+                return Err(diagnostics);
+            }
+        };
+
+        let arms_span = self.cursor().token_stream().span();
+        if let Ok((brace_token, content)) = (|| {
+            let content;
+            Ok((braced!(content in self), content))
+        })() {
+            let mut arms = Vec::new();
+            while !content.is_empty() {
+                let arm_span = content.cursor().token_stream().span();
+                let arm;
+                (arm, diagnostics) = MyParse::<HtmlMatchArm<Inner>>::my_parse(
+                    &content,
+                    identity,
+                    |diagnostic| {
+                        diagnostic
+                            .span_note(arm_span, "while parsing match arm")
+                            .span_note(arms_span, "while parsing match arms")
+                    },
+                    diagnostics,
+                )?;
+                arms.push(arm);
+            }
+            Ok((
+                HtmlMatch {
+                    match_token,
+                    expr,
+                    brace_token,
+                    arms,
+                },
+                diagnostics,
+            ))
+        } else {
+            diagnostics.push(arms_span.error("expected { }"));
+            Err(diagnostics)
+        }
+    }
+}
+
+impl<Inner: Debug> MyParse<HtmlMatchArm<Inner>> for ParseStream<'_>
+where
+    for<'a> ParseStream<'a>: MyParse<Inner>,
+{
+    #[instrument(err(Debug), ret, name = "HtmlMatchArm<Inner>")]
+    fn inner_my_parse(self) -> Result<(HtmlMatchArm<Inner>, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+
+        let pat = match Pat::parse_multi_with_leading_vert(self) {
+            Ok(value) => value,
+            Err(error) => {
+                diagnostics.push(error.into());
+                return Err(diagnostics);
+            }
+        };
+        // the guard ends at the `=>`
+        let guard = if self.peek(Token![if]) {
+            let if_token: Token![if];
+            (if_token, diagnostics) =
+                MyParse::<Token![if]>::my_parse(self, identity, identity, diagnostics)?;
+            let result = self.step(|cursor| {
+                let mut rest = *cursor;
+                let mut tokens = TokenStream::new();
+                while let Some((tt, next)) = rest.token_tree() {
+                    match &tt {
+                        TokenTree::Punct(punct)
+                            if punct.as_char() == '='
+                                && matches!(next.punct(), Some((punct, _)) if punct.as_char() == '>') =>
+                        {
+                            return Ok((tokens, rest));
+                        }
+                        _ => {
+                            tokens.extend(std::iter::once(tt));
+                            rest = next;
+                        }
+                    }
+                }
+                Err(cursor.error("no => was found after this point"))
+            });
+            match result {
+                Ok(value) => Some((if_token, value)),
+                Err(error) => {
+                    diagnostics.push(error.into());
+                    return Err(diagnostics);
+                }
+            }
+        } else {
+            None
+        };
+        let fat_arrow_token: Token![=>];
+        (fat_arrow_token, diagnostics) =
+            MyParse::<Token![=>]>::my_parse(self, identity, identity, diagnostics)?;
+
+        let body_span = self.cursor().token_stream().span();
+        let body = if let Ok((brace_token, content)) = (|| {
+            let content;
+            Ok((braced!(content in self), content))
+        })() {
+            // TODO FIXME check fully parsed
+            let result;
+            (result, diagnostics) = MyParse::<Inner>::my_parse(
+                &content,
+                identity,
+                |diagnostic| diagnostic.span_note(body_span, "while parsing match arm body"),
+                diagnostics,
+            )?;
+            (brace_token, result)
+        } else {
+            diagnostics.push(body_span.error("expected { }"));
+            return Err(diagnostics);
+        };
+        let comma;
+        (comma, diagnostics) =
+            MyParse::<Option<Token![,]>>::my_parse(self, identity, identity, diagnostics)?;
+        Ok((
+            HtmlMatchArm {
+                pat,
+                guard,
+                fat_arrow_token,
+                body,
+                comma,
+            },
+            diagnostics,
+        ))
+    }
+}
+
 impl MyParse<Vec<HtmlInElementContext>> for ParseStream<'_> {
     #[instrument(err(Debug), ret, name = "HtmlTopLevel")]
     fn inner_my_parse(
@@ -850,6 +1434,7 @@ impl MyParse<Vec<HtmlInElementContext>> for ParseStream<'_> {
         let mut diagnostics = Vec::new();
 
         let mut children = Vec::new();
+        #[allow(clippy::nonminimal_bool)]
         while !self.is_empty() && !(self.peek(Token![<]) && self.peek2(Token![/])) {
             let child_start_span = self.cursor().token_stream().span();
             let result;
@@ -906,6 +1491,130 @@ impl MyParse<Vec<HtmlInAttributeContext>> for ParseStream<'_> {
     }
 }
 
+/// `[ ]` is a class list if it contains a `,` or a `=>`, otherwise its content is concatenated.
+fn is_class_list(input: ParseStream) -> bool {
+    let mut rest = input.cursor();
+    while let Some((tt, next)) = rest.token_tree() {
+        if let TokenTree::Punct(punct) = &tt {
+            if punct.as_char() == ','
+                || punct.as_char() == '='
+                    && matches!(next.punct(), Some((punct, _)) if punct.as_char() == '>')
+            {
+                return true;
+            }
+        }
+        rest = next;
+    }
+    false
+}
+
+impl MyParse<Vec<HtmlClass>> for ParseStream<'_> {
+    #[instrument(err(Debug), ret, name = "HtmlClassList")]
+    fn inner_my_parse(self) -> Result<(Vec<HtmlClass>, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let span = self.cursor().token_stream().span();
+        let mut diagnostics = Vec::new();
+        let mut classes = Vec::new();
+        while !self.is_empty() {
+            let class_start_span = self.cursor().token_stream().span();
+            let class;
+            (class, diagnostics) = MyParse::<HtmlClass>::my_parse(
+                self,
+                identity,
+                |diagnostic| {
+                    diagnostic
+                        .span_note(class_start_span, "while parsing class")
+                        .span_note(span, "while parsing class list")
+                },
+                diagnostics,
+            )?;
+            classes.push(class);
+        }
+        Ok((classes, diagnostics))
+    }
+}
+
+impl MyParse<HtmlClass> for ParseStream<'_> {
+    #[instrument(err(Debug), ret, name = "HtmlClass")]
+    fn inner_my_parse(self) -> Result<(HtmlClass, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let lookahead = self.lookahead1();
+        let class = if lookahead.peek(LitStr) {
+            let value;
+            (value, diagnostics) =
+                MyParse::my_parse(self, HtmlClassName::Literal, identity, diagnostics)?;
+            value
+        } else if lookahead.peek(Paren) {
+            let then_span = self.cursor().token_stream().span();
+            if let Ok((paren, content)) = (|| {
+                let content;
+                Ok((parenthesized!(content in self), content))
+            })() {
+                // TODO FIXME check fully parsed
+                HtmlClassName::ComputedValue((paren, content.parse().unwrap()))
+            } else {
+                diagnostics.push(then_span.error("expected ( )"));
+                return Err(diagnostics);
+            }
+        } else {
+            diagnostics.push(Diagnostic::from(lookahead.error()));
+            return Err(diagnostics);
+        };
+        let condition = if self.peek(Token![=>]) {
+            let fat_arrow_token: FatArrow;
+            (fat_arrow_token, diagnostics) =
+                MyParse::my_parse(self, identity, identity, diagnostics)?;
+            // the condition ends at the `,` before the next class
+            let condition = self.step(|cursor| {
+                let mut rest = *cursor;
+                let mut tokens = TokenStream::new();
+                while let Some((tt, next)) = rest.token_tree() {
+                    match &tt {
+                        TokenTree::Punct(punct) if punct.as_char() == ',' => break,
+                        _ => {
+                            tokens.extend(std::iter::once(tt));
+                            rest = next;
+                        }
+                    }
+                }
+                if tokens.is_empty() {
+                    Err(cursor.error("expected a condition after =>"))
+                } else {
+                    Ok((tokens, rest))
+                }
+            });
+            match condition {
+                Ok(condition) => Some((fat_arrow_token, condition)),
+                Err(error) => {
+                    diagnostics.push(error.into());
+                    return Err(diagnostics);
+                }
+            }
+        } else {
+            None
+        };
+        let comma;
+        (comma, diagnostics) =
+            MyParse::<Option<Token![,]>>::my_parse(self, identity, identity, diagnostics)?;
+        if comma.is_none() && !self.is_empty() {
+            diagnostics.push(
+                self.cursor()
+                    .token_stream()
+                    .span()
+                    .error("expected `,` or `=>`"),
+            );
+            return Err(diagnostics);
+        }
+        Ok((
+            HtmlClass {
+                class,
+                condition,
+                comma,
+            },
+            diagnostics,
+        ))
+    }
+}
+
 impl MyParse<Vec<HtmlInAttributeValueContext>> for ParseStream<'_> {
     #[instrument(err(Debug), ret, name = "HtmlAttributeValue")]
     fn inner_my_parse(
@@ -939,16 +1648,35 @@ impl MyParse<Vec<HtmlInAttributeValueContext>> for ParseStream<'_> {
     }
 }
 
+/// Joins e.g. `aria-current`, `my-widget` or `svg:rect` back together.
+pub fn name_to_string(name: &Punctuated<Ident, DashOrColon>) -> String {
+    name.pairs()
+        .map(|p| {
+            p.value().unraw().to_string()
+                + match p.punct() {
+                    Some(DashOrColon::Colon(_)) => ":",
+                    Some(DashOrColon::Dash(_)) => "-",
+                    None => "",
+                }
+        })
+        .collect()
+}
+
+pub fn name_span(name: &Punctuated<Ident, DashOrColon>) -> proc_macro2::Span {
+    let first = name.first().unwrap().span();
+    first.join(name.last().unwrap().span()).unwrap_or(first)
+}
+
 impl HtmlTag {
     #[instrument(ret, name = "HtmlTag::span")]
     pub fn span(&self) -> proc_macro2::Span {
         if let Some(exclamation) = self.exclamation {
             exclamation
                 .span()
-                .join(self.name.span())
-                .unwrap_or_else(|| self.name.span())
+                .join(name_span(&self.name))
+                .unwrap_or_else(|| name_span(&self.name))
         } else {
-            self.name.span()
+            name_span(&self.name)
         }
     }
 }
@@ -963,7 +1691,7 @@ impl Display for HtmlTag {
             } else {
                 String::new()
             },
-            self.name
+            name_to_string(&self.name)
         )
     }
 }
@@ -993,6 +1721,844 @@ impl MyParse<HtmlTag> for ParseStream<'_> {
     }
 }
 
+/// Elements that never have children and therefore no closing tag.
+pub const VOID_ELEMENTS: &[&str] = &[
+    "!doctype", "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr",
+];
+
+/// HTML elements that need a closing tag, so `/>` would leave them open.
+const HTML_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "body",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "html",
+    "i",
+    "iframe",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "main",
+    "map",
+    "mark",
+    "menu",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "script",
+    "search",
+    "section",
+    "select",
+    "slot",
+    "small",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "u",
+    "ul",
+    "var",
+    "video",
+];
+
+/// Elements that end foreign content when they appear inside of `<svg>` or `<math>`.
+/// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+const BREAKOUT_ELEMENTS: &[&str] = &[
+    "b",
+    "big",
+    "blockquote",
+    "body",
+    "br",
+    "center",
+    "code",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "listing",
+    "menu",
+    "meta",
+    "nobr",
+    "ol",
+    "p",
+    "pre",
+    "ruby",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "strike",
+    "sub",
+    "sup",
+    "table",
+    "tt",
+    "u",
+    "ul",
+    "var",
+];
+
+/// SVG element names that aren't all lowercase.
+/// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+const SVG_ELEMENTS: &[&str] = &[
+    "altGlyph",
+    "altGlyphDef",
+    "altGlyphItem",
+    "animateColor",
+    "animateMotion",
+    "animateTransform",
+    "clipPath",
+    "feBlend",
+    "feColorMatrix",
+    "feComponentTransfer",
+    "feComposite",
+    "feConvolveMatrix",
+    "feDiffuseLighting",
+    "feDisplacementMap",
+    "feDistantLight",
+    "feDropShadow",
+    "feFlood",
+    "feFuncA",
+    "feFuncB",
+    "feFuncG",
+    "feFuncR",
+    "feGaussianBlur",
+    "feImage",
+    "feMerge",
+    "feMergeNode",
+    "feMorphology",
+    "feOffset",
+    "fePointLight",
+    "feSpecularLighting",
+    "feSpotLight",
+    "feTile",
+    "feTurbulence",
+    "foreignObject",
+    "glyphRef",
+    "linearGradient",
+    "radialGradient",
+    "textPath",
+];
+
+/// SVG attribute names that aren't all lowercase.
+/// https://html.spec.whatwg.org/multipage/parsing.html#adjust-svg-attributes
+const SVG_ATTRIBUTES: &[&str] = &[
+    "attributeName",
+    "attributeType",
+    "baseFrequency",
+    "baseProfile",
+    "calcMode",
+    "clipPathUnits",
+    "diffuseConstant",
+    "edgeMode",
+    "filterUnits",
+    "glyphRef",
+    "gradientTransform",
+    "gradientUnits",
+    "kernelMatrix",
+    "kernelUnitLength",
+    "keyPoints",
+    "keySplines",
+    "keyTimes",
+    "lengthAdjust",
+    "limitingConeAngle",
+    "markerHeight",
+    "markerUnits",
+    "markerWidth",
+    "maskContentUnits",
+    "maskUnits",
+    "numOctaves",
+    "pathLength",
+    "patternContentUnits",
+    "patternTransform",
+    "patternUnits",
+    "pointsAtX",
+    "pointsAtY",
+    "pointsAtZ",
+    "preserveAlpha",
+    "preserveAspectRatio",
+    "primitiveUnits",
+    "refX",
+    "refY",
+    "repeatCount",
+    "repeatDur",
+    "requiredExtensions",
+    "requiredFeatures",
+    "specularConstant",
+    "specularExponent",
+    "spreadMethod",
+    "startOffset",
+    "stdDeviation",
+    "stitchTiles",
+    "surfaceScale",
+    "systemLanguage",
+    "tableValues",
+    "targetX",
+    "targetY",
+    "textLength",
+    "viewBox",
+    "viewTarget",
+    "xChannelSelector",
+    "yChannelSelector",
+    "zoomAndPan",
+];
+
+/// MathML attribute names that aren't all lowercase.
+const MATHML_ATTRIBUTES: &[&str] = &["definitionURL"];
+
+/// The only namespaced attributes, they are allowed on SVG and MathML elements.
+/// https://html.spec.whatwg.org/multipage/parsing.html#adjust-foreign-attributes
+const FOREIGN_ATTRIBUTES: &[&str] = &[
+    "xlink:actuate",
+    "xlink:arcrole",
+    "xlink:href",
+    "xlink:role",
+    "xlink:show",
+    "xlink:title",
+    "xlink:type",
+    "xml:lang",
+    "xml:space",
+    "xmlns:xlink",
+];
+
+/// Whether elements are parsed as HTML or as foreign content.
+#[derive(Debug, Clone, Copy)]
+enum Namespace<'a> {
+    Html,
+    /// Inside of the `<svg>` element.
+    Svg(&'a HtmlTag),
+    /// Inside of the `<math>` element.
+    MathMl(&'a HtmlTag),
+}
+
+/// Walks the elements from the top of the template as the namespace depends on the ancestors.
+///
+/// `/>` is only allowed on void elements and in SVG or MathML. Elements that aren't known HTML elements are assumed to be
+/// foreign content that is inserted into an `<svg>` or `<math>` of another template.
+fn check_namespaces(children: &[HtmlInElementContext], diagnostics: &mut Vec<Diagnostic>) {
+    check_namespaces_inner(children, Namespace::Html, diagnostics);
+}
+
+fn check_namespaces_inner<'a>(
+    children: &'a [HtmlInElementContext],
+    namespace: Namespace<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for child in children {
+        match child {
+            HtmlInElementContext::Element(element) => {
+                let name = element.open_tag_name.to_string();
+                let namespace = match (namespace, name.as_str()) {
+                    (_, "svg") => Namespace::Svg(&element.open_tag_name),
+                    (_, "math") => Namespace::MathMl(&element.open_tag_name),
+                    (namespace, _) => namespace,
+                };
+                match namespace {
+                    Namespace::Html => check_html_element(element, &name, diagnostics),
+                    Namespace::Svg(_) | Namespace::MathMl(_) => {
+                        check_foreign_element(element, &name, namespace, diagnostics)
+                    }
+                }
+                if let Some((children, ..)) = &element.children {
+                    // integration points contain HTML again
+                    let namespace = match (namespace, name.as_str()) {
+                        (Namespace::Svg(_), "foreignObject" | "desc" | "title")
+                        | (Namespace::MathMl(_), "mi" | "mo" | "mn" | "ms" | "mtext") => {
+                            Namespace::Html
+                        }
+                        (namespace, _) => namespace,
+                    };
+                    check_namespaces_inner(children, namespace, diagnostics);
+                }
+            }
+            HtmlInElementContext::If(html_if) => {
+                check_namespaces_if(html_if, namespace, diagnostics)
+            }
+            HtmlInElementContext::For(html_for) => {
+                check_namespaces_inner(&html_for.body.1, namespace, diagnostics)
+            }
+            HtmlInElementContext::While(html_while) => {
+                check_namespaces_inner(&html_while.body.1, namespace, diagnostics)
+            }
+            HtmlInElementContext::Match(html_match) => {
+                for arm in &html_match.arms {
+                    check_namespaces_inner(&arm.body.1, namespace, diagnostics);
+                }
+            }
+            HtmlInElementContext::Literal(_)
+            | HtmlInElementContext::Computation(_)
+            | HtmlInElementContext::ComputedValue(_)
+            | HtmlInElementContext::Let(_)
+            | HtmlInElementContext::Comment(_) => {}
+        }
+    }
+}
+
+fn check_namespaces_if<'a>(
+    html_if: &'a HtmlIf<Vec<HtmlInElementContext>>,
+    namespace: Namespace<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    check_namespaces_inner(&html_if.then_branch.1, namespace, diagnostics);
+    match &html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => check_namespaces_if(html_if, namespace, diagnostics),
+        Some((_, HtmlElse::Else(_, inner))) => {
+            check_namespaces_inner(inner, namespace, diagnostics)
+        }
+        None => {}
+    }
+}
+
+fn check_html_element(element: &HtmlElement, name: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let known = HTML_ELEMENTS.contains(&name) || VOID_ELEMENTS.contains(&name);
+    if let Some(self_closing) = element.self_closing {
+        if !VOID_ELEMENTS.contains(&name) && (known || name.contains('-')) {
+            diagnostics.push(
+                self_closing
+                    .span
+                    .error(format!("<{name}/> is not self-closing in HTML"))
+                    .help(format!("write <{name}></{name}> instead"))
+                    .span_note(
+                        element.open_tag_name.span(),
+                        "only void elements and SVG or MathML elements can use />",
+                    ),
+            );
+        }
+    }
+    if known {
+        let mut has_lang = false;
+        for_each_attribute(&element.attributes, &mut |attribute| {
+            has_lang |= name_to_string(attribute) == "lang";
+        });
+        for_each_attribute(&element.attributes, &mut |attribute| {
+            let attribute_text = name_to_string(attribute);
+            // HTML allows `xml:lang` next to a `lang` attribute with the same value
+            if attribute_text == "xml:lang" {
+                if !has_lang {
+                    diagnostics.push(
+                        name_span(attribute)
+                            .error("xml:lang on an HTML element needs a lang attribute")
+                            .help("add lang with the same value or use lang instead"),
+                    );
+                }
+            } else if ["xlink:", "xmlns:", "xml:"]
+                .iter()
+                .any(|prefix| attribute_text.starts_with(prefix))
+            {
+                // other colons like hx-on:click or v-bind:class are plain attribute names
+                diagnostics.push(
+                    name_span(attribute)
+                        .error(format!(
+                            "namespaced attribute {attribute_text} is only allowed on SVG and MathML elements"
+                        ))
+                        .span_note(element.open_tag_name.span(), format!("<{name}> is an HTML element")),
+                );
+            }
+        });
+    }
+}
+
+fn check_foreign_element(
+    element: &HtmlElement,
+    name: &str,
+    namespace: Namespace,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let (root, language, case_sensitive_attributes, integration_point) = match namespace {
+        Namespace::Html => return,
+        Namespace::Svg(root) => (root, "SVG", SVG_ATTRIBUTES, "<foreignObject>"),
+        Namespace::MathMl(root) => (root, "MathML", MATHML_ATTRIBUTES, "<mtext>"),
+    };
+    let root_name = root.to_string();
+    if BREAKOUT_ELEMENTS.contains(&name) {
+        diagnostics.push(
+            element
+                .open_tag_name
+                .span()
+                .error(format!(
+                    "<{name}> is an HTML element and would end the <{root_name}> element early"
+                ))
+                .help(format!("put HTML content into {integration_point}"))
+                .span_note(root.span(), format!("<{root_name}> opened here")),
+        );
+    } else if let Namespace::Svg(_) = namespace {
+        if let Some(expected) = SVG_ELEMENTS
+            .iter()
+            .find(|expected| expected.eq_ignore_ascii_case(name) && **expected != name)
+        {
+            diagnostics.push(
+                element
+                    .open_tag_name
+                    .span()
+                    .error(format!(
+                        "SVG element names are case-sensitive, did you mean <{expected}>?"
+                    ))
+                    .span_note(root.span(), format!("<{root_name}> opened here")),
+            );
+        }
+    }
+    for_each_attribute(&element.attributes, &mut |attribute| {
+        let attribute_text = name_to_string(attribute);
+        if attribute_text.contains(':') {
+            if !FOREIGN_ATTRIBUTES.contains(&attribute_text.as_str()) {
+                diagnostics.push(
+                    name_span(attribute)
+                        .error(format!("unknown namespaced attribute {attribute_text}"))
+                        .help(format!(
+                            "the namespaced attributes are {}",
+                            FOREIGN_ATTRIBUTES.join(", ")
+                        )),
+                );
+            }
+        } else if let Some(expected) = case_sensitive_attributes.iter().find(|expected| {
+            expected.eq_ignore_ascii_case(&attribute_text) && **expected != attribute_text
+        }) {
+            diagnostics.push(
+                name_span(attribute)
+                    .error(format!(
+                        "{language} attribute names are case-sensitive, did you mean {expected}?"
+                    ))
+                    .span_note(root.span(), format!("<{root_name}> opened here")),
+            );
+        }
+    });
+}
+
+/// Calls `fun` with the name of every attribute that is set on some path through the control flow.
+///
+/// The names of spread attributes and inserted templates are only known at runtime.
+pub fn for_each_attribute<'a>(
+    attributes: &'a [HtmlInAttributeContext],
+    fun: &mut impl FnMut(&'a Punctuated<Ident, DashOrColon>),
+) {
+    for attribute in attributes {
+        match attribute {
+            HtmlInAttributeContext::Literal(name, _)
+            | HtmlInAttributeContext::Optional(name, ..) => fun(name),
+            HtmlInAttributeContext::If(html_if) => for_each_attribute_if(html_if, fun),
+            HtmlInAttributeContext::For(html_for) => for_each_attribute(&html_for.body.1, fun),
+            HtmlInAttributeContext::While(html_while) => {
+                for_each_attribute(&html_while.body.1, fun)
+            }
+            HtmlInAttributeContext::Match(html_match) => {
+                for arm in &html_match.arms {
+                    for_each_attribute(&arm.body.1, fun);
+                }
+            }
+            HtmlInAttributeContext::Computation(_)
+            | HtmlInAttributeContext::Spread(..)
+            | HtmlInAttributeContext::Let(_) => {}
+        }
+    }
+}
+
+fn for_each_attribute_if<'a>(
+    html_if: &'a HtmlIf<Vec<HtmlInAttributeContext>>,
+    fun: &mut impl FnMut(&'a Punctuated<Ident, DashOrColon>),
+) {
+    for_each_attribute(&html_if.then_branch.1, fun);
+    match &html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => for_each_attribute_if(html_if, fun),
+        Some((_, HtmlElse::Else(_, inner))) => for_each_attribute(inner, fun),
+        None => {}
+    }
+}
+
+/// `<script>` and `<style>` contain raw text, so only things that can be escaped for JavaScript or CSS are allowed.
+///
+/// Adjacent literals end up next to each other in the output, so the end of the text before every literal is carried
+/// along as `tails` to also catch a `</script` that is split across literals, branches or loop iterations.
+/// Computed values are escaped and can't contain a `<`, so they end every partial match.
+fn check_raw_text(
+    children: &[HtmlInElementContext],
+    element: &HtmlTag,
+    mut tails: BTreeSet<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> BTreeSet<String> {
+    let name = element.to_string();
+    let end_tag = format!("</{name}");
+    for child in children {
+        match child {
+            HtmlInElementContext::Literal(literal) => {
+                let value = literal.value().to_ascii_lowercase();
+                let texts: Vec<String> =
+                    tails.iter().map(|tail| format!("{tail}{value}")).collect();
+                if texts.iter().any(|text| text.contains(&end_tag)) {
+                    diagnostics.push(
+                        literal
+                            .span()
+                            .error(format!("</{name} would end the <{name}> element early"))
+                            .span_note(element.span(), format!("<{name}> opened here")),
+                    );
+                }
+                tails = texts
+                    .into_iter()
+                    .map(|text| {
+                        let skip = text.chars().count().saturating_sub(end_tag.len() - 1);
+                        text.chars().skip(skip).collect()
+                    })
+                    .collect();
+            }
+            HtmlInElementContext::ComputedValue(_) => tails = BTreeSet::from([String::new()]),
+            HtmlInElementContext::Let(_) => {}
+            HtmlInElementContext::Computation((brace, _)) => diagnostics.push(
+                brace
+                    .span
+                    .join()
+                    .error(format!(
+                        "templates can't be inserted into <{name}> because its content is raw text"
+                    ))
+                    .span_note(element.span(), format!("<{name}> opened here"))
+                    .help("use ( value ) to insert an escaped value"),
+            ),
+            HtmlInElementContext::Element(child) => diagnostics.push(
+                child
+                    .open_tag_name
+                    .span()
+                    .error(format!(
+                        "elements can't be nested in <{name}> because its content is raw text"
+                    ))
+                    .span_note(element.span(), format!("<{name}> opened here")),
+            ),
+            HtmlInElementContext::Comment(comment) => diagnostics.push(
+                comment
+                    .open_start
+                    .span
+                    .error(format!(
+                        "comments can't be nested in <{name}> because its content is raw text"
+                    ))
+                    .span_note(element.span(), format!("<{name}> opened here")),
+            ),
+            HtmlInElementContext::If(html_if) => {
+                tails = check_raw_text_if(html_if, element, tails, diagnostics)
+            }
+            HtmlInElementContext::For(html_for) => {
+                tails = check_raw_text_loop(&html_for.body.1, element, tails, diagnostics)
+            }
+            HtmlInElementContext::While(html_while) => {
+                tails = check_raw_text_loop(&html_while.body.1, element, tails, diagnostics)
+            }
+            HtmlInElementContext::Match(html_match) => {
+                let mut added = BTreeSet::new();
+                for arm in &html_match.arms {
+                    added.extend(check_raw_text(
+                        &arm.body.1,
+                        element,
+                        tails.clone(),
+                        diagnostics,
+                    ));
+                }
+                tails = added;
+            }
+        }
+    }
+    tails
+}
+
+fn check_raw_text_if(
+    html_if: &HtmlIf<Vec<HtmlInElementContext>>,
+    element: &HtmlTag,
+    tails: BTreeSet<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> BTreeSet<String> {
+    let mut added = check_raw_text(&html_if.then_branch.1, element, tails.clone(), diagnostics);
+    match &html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => {
+            added.extend(check_raw_text_if(html_if, element, tails, diagnostics))
+        }
+        Some((_, HtmlElse::Else(_, inner))) => {
+            added.extend(check_raw_text(inner, element, tails, diagnostics))
+        }
+        None => added.extend(tails),
+    }
+    added
+}
+
+/// The body of a loop can follow the text before the loop or another iteration of itself.
+fn check_raw_text_loop(
+    body: &[HtmlInElementContext],
+    element: &HtmlTag,
+    tails: BTreeSet<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> BTreeSet<String> {
+    // there are only finitely many tails, so this reaches a fixed point
+    let mut entry = tails;
+    loop {
+        let mut next = entry.clone();
+        next.extend(check_raw_text(
+            body,
+            element,
+            entry.clone(),
+            &mut Vec::new(),
+        ));
+        if next == entry {
+            break;
+        }
+        entry = next;
+    }
+    let mut exit = check_raw_text(body, element, entry.clone(), diagnostics);
+    exit.extend(entry);
+    exit
+}
+
+/// Browsers ignore every occurrence of an attribute after the first one, so attributes may only be set once on every path through the control flow.
+///
+/// Spread attributes and inserted templates are only known at runtime and aren't checked.
+fn check_duplicate_attributes(
+    attributes: &[HtmlInAttributeContext],
+    seen: &mut Vec<(String, proc_macro2::Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for attribute in attributes {
+        match attribute {
+            HtmlInAttributeContext::Literal(name, _)
+            | HtmlInAttributeContext::Optional(name, ..) => {
+                let name_text = name_to_string(name).to_ascii_lowercase();
+                let span = name_span(name);
+                if let Some((_, first)) = seen.iter().find(|(seen, _)| *seen == name_text) {
+                    diagnostics.push(
+                        span.error(format!("duplicate attribute {name_text}"))
+                            .span_note(*first, format!("{name_text} is already set here")),
+                    );
+                } else {
+                    seen.push((name_text, span));
+                }
+            }
+            HtmlInAttributeContext::If(html_if) => {
+                let mut added = Vec::new();
+                check_duplicate_attributes_if(html_if, seen, &mut added, diagnostics);
+                seen.extend(added);
+            }
+            HtmlInAttributeContext::For(html_for) => {
+                check_duplicate_attributes_loop(
+                    &html_for.body.1,
+                    html_for.for_token.span,
+                    seen,
+                    diagnostics,
+                );
+            }
+            HtmlInAttributeContext::While(html_while) => {
+                check_duplicate_attributes_loop(
+                    &html_while.body.1,
+                    html_while.while_token.span,
+                    seen,
+                    diagnostics,
+                );
+            }
+            HtmlInAttributeContext::Match(html_match) => {
+                let mut added = Vec::new();
+                for arm in &html_match.arms {
+                    check_duplicate_attributes_branch(&arm.body.1, seen, &mut added, diagnostics);
+                }
+                seen.extend(added);
+            }
+            HtmlInAttributeContext::Computation(_)
+            | HtmlInAttributeContext::Spread(..)
+            | HtmlInAttributeContext::Let(_) => {}
+        }
+    }
+}
+
+// branches are checked against what was set before them but not against each other, as only one of them runs
+fn check_duplicate_attributes_branch(
+    attributes: &[HtmlInAttributeContext],
+    seen: &[(String, proc_macro2::Span)],
+    added: &mut Vec<(String, proc_macro2::Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut branch = seen.to_vec();
+    check_duplicate_attributes(attributes, &mut branch, diagnostics);
+    added.extend(branch.drain(seen.len()..));
+}
+
+/// A loop can run more than once, so every attribute it sets would be duplicated by the next iteration.
+fn check_duplicate_attributes_loop(
+    attributes: &[HtmlInAttributeContext],
+    loop_span: proc_macro2::Span,
+    seen: &mut Vec<(String, proc_macro2::Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut added = Vec::new();
+    check_duplicate_attributes_branch(attributes, seen, &mut added, diagnostics);
+    for (name_text, span) in &added {
+        diagnostics.push(
+            span.error(format!(
+                "attribute {name_text} would be set again in the next iteration"
+            ))
+            .span_note(loop_span, "this loop can run more than once")
+            .help("set the attribute outside of the loop"),
+        );
+    }
+    seen.extend(added);
+}
+
+fn check_duplicate_attributes_if(
+    html_if: &HtmlIf<Vec<HtmlInAttributeContext>>,
+    seen: &[(String, proc_macro2::Span)],
+    added: &mut Vec<(String, proc_macro2::Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    check_duplicate_attributes_branch(&html_if.then_branch.1, seen, added, diagnostics);
+    match &html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => {
+            check_duplicate_attributes_if(html_if, seen, added, diagnostics)
+        }
+        Some((_, HtmlElse::Else(_, inner))) => {
+            check_duplicate_attributes_branch(inner, seen, added, diagnostics)
+        }
+        None => {}
+    }
+}
+
+impl MyParse<HtmlComment> for ParseStream<'_> {
+    #[instrument(err(Debug), ret, name = "HtmlComment")]
+    fn inner_my_parse(self) -> Result<(HtmlComment, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+
+        let open_start;
+        (open_start, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+        let exclamation;
+        (exclamation, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+        let open_dashes = {
+            let first;
+            (first, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            let second;
+            (second, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            (first, second)
+        };
+        let mut content = Vec::new();
+        while !(self.peek(Token![-]) && self.peek2(Token![-]) && self.peek3(Token![>])) {
+            let lookahead = self.lookahead1();
+            if lookahead.peek(LitStr) {
+                let literal;
+                (literal, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+                content.push(HtmlInCommentContext::Literal(literal));
+            } else if lookahead.peek(Paren) {
+                let then_span = self.cursor().token_stream().span();
+                if let Ok((paren, inner)) = (|| {
+                    let inner;
+                    Ok((parenthesized!(inner in self), inner))
+                })() {
+                    content.push(HtmlInCommentContext::ComputedValue((
+                        paren,
+                        inner.parse().unwrap(),
+                    )));
+                } else {
+                    diagnostics.push(then_span.error("expected ( )"));
+                    return Err(diagnostics);
+                }
+            } else {
+                diagnostics.push(
+                    Diagnostic::from(lookahead.error())
+                        .help("comments are written as <!-- \"text\" (value) -->"),
+                );
+                return Err(diagnostics);
+            }
+        }
+        let close_dashes = {
+            let first;
+            (first, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            let second;
+            (second, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            (first, second)
+        };
+        let close_end;
+        (close_end, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+        Ok((
+            HtmlComment {
+                open_start,
+                exclamation,
+                open_dashes,
+                content,
+                close_dashes,
+                close_end,
+            },
+            diagnostics,
+        ))
+    }
+}
+
 impl MyParse<HtmlElement> for ParseStream<'_> {
     #[instrument(err(Debug), ret, name = "HtmlElement")]
     fn inner_my_parse(self) -> Result<(HtmlElement, Vec<Diagnostic>), Vec<Diagnostic>> {
@@ -1011,7 +2577,7 @@ impl MyParse<HtmlElement> for ParseStream<'_> {
         let open_tag_name_text = open_tag_name.to_string();
         let attributes = {
             let mut attributes = Vec::new();
-            while !self.peek(Token![>]) {
+            while !(self.peek(Token![>]) || self.peek(Token![/]) && self.peek2(Token![>])) {
                 let attribute_start_span = self.cursor().token_stream().span();
                 attributes.push({
                     let value;
@@ -1028,18 +2594,19 @@ impl MyParse<HtmlElement> for ParseStream<'_> {
             }
             attributes
         };
+        check_duplicate_attributes(&attributes, &mut Vec::new(), &mut diagnostics);
+        let self_closing: Option<Token![/]> = {
+            let value;
+            (value, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
+            value
+        };
         let open_end = {
             let value;
             (value, diagnostics) = MyParse::my_parse(self, identity, identity, diagnostics)?;
             value
         };
         let children = {
-            if ![
-                "!doctype", "area", "base", "br", "col", "embed", "hr", "img", "input", "link",
-                "meta", "param", "source", "track", "wbr",
-            ]
-            .contains(&open_tag_name_text.as_str())
-            {
+            if self_closing.is_none() && !VOID_ELEMENTS.contains(&open_tag_name_text.as_str()) {
                 Some((
                     {
                         let value;
@@ -1089,11 +2656,29 @@ impl MyParse<HtmlElement> for ParseStream<'_> {
                 None
             }
         };
+        if let Some((children, ..)) = &children {
+            let children: &Vec<HtmlInElementContext> = children;
+            if ["script", "style"].contains(&open_tag_name_text.as_str()) {
+                check_raw_text(
+                    children,
+                    &open_tag_name,
+                    BTreeSet::from([String::new()]),
+                    &mut diagnostics,
+                );
+            }
+            #[cfg(feature = "validation")]
+            crate::validation::check_content_model(&open_tag_name, children, &mut diagnostics);
+        }
+        #[cfg(feature = "validation")]
+        if open_tag_name.exclamation.is_none() {
+            crate::validation::check_attributes(&open_tag_name, &attributes, &mut diagnostics);
+        }
         Ok((
             HtmlElement {
                 open_start,
                 open_tag_name,
                 attributes,
+                self_closing,
                 open_end,
                 children,
             },
@@ -0,0 +1,862 @@
+//! Optional checks of the HTML spec that are enabled with the `validation` feature.
+//!
+//! Only direct children and the attributes in the same template are checked, an element at the top level of a template has no known parent.
+
+use proc_macro2_diagnostics::{Diagnostic, SpanDiagnosticExt};
+use syn::{punctuated::Punctuated, Ident};
+
+use crate::parse::{
+    for_each_attribute, name_span, name_to_string, DashOrColon, HtmlElement, HtmlElse, HtmlIf,
+    HtmlInAttributeContext, HtmlInElementContext, HtmlTag,
+};
+
+/// Elements that can only contain phrasing content.
+/// https://html.spec.whatwg.org/multipage/dom.html#phrasing-content
+const PHRASING_PARENTS: &[&str] = &[
+    "abbr", "b", "bdi", "bdo", "button", "cite", "code", "data", "dfn", "em", "h1", "h2", "h3",
+    "h4", "h5", "h6", "i", "kbd", "label", "mark", "output", "p", "pre", "q", "s", "samp", "small",
+    "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// Elements that are flow content but not phrasing content.
+const FLOW_ONLY: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "search",
+    "section",
+    "table",
+    "ul",
+];
+
+/// https://html.spec.whatwg.org/multipage/dom.html#metadata-content
+const METADATA: &[&str] = &[
+    "base", "link", "meta", "noscript", "script", "style", "template", "title",
+];
+
+/// Elements that are only allowed in specific parents.
+const PARENTS: &[(&str, &[&str])] = &[
+    ("li", &["ul", "ol", "menu"]),
+    ("dt", &["dl", "div"]),
+    ("dd", &["dl", "div"]),
+    ("caption", &["table"]),
+    ("colgroup", &["table"]),
+    ("thead", &["table"]),
+    ("tbody", &["table"]),
+    ("tfoot", &["table"]),
+    ("col", &["colgroup"]),
+    ("tr", &["thead", "tbody", "tfoot"]),
+    ("td", &["tr"]),
+    ("th", &["tr"]),
+    ("option", &["select", "datalist", "optgroup"]),
+    ("optgroup", &["select"]),
+    ("figcaption", &["figure"]),
+    ("legend", &["fieldset"]),
+    ("summary", &["details"]),
+    ("title", &["head"]),
+    ("base", &["head"]),
+    ("head", &["html"]),
+    ("body", &["html"]),
+];
+
+/// https://html.spec.whatwg.org/multipage/dom.html#global-attributes
+const GLOBAL_ATTRIBUTES: &[&str] = &[
+    "accesskey",
+    "autocapitalize",
+    "autocorrect",
+    "autofocus",
+    "class",
+    "contenteditable",
+    "dir",
+    "draggable",
+    "enterkeyhint",
+    "hidden",
+    "id",
+    "inert",
+    "inputmode",
+    "is",
+    "itemid",
+    "itemprop",
+    "itemref",
+    "itemscope",
+    "itemtype",
+    "lang",
+    "nonce",
+    "popover",
+    "role",
+    "slot",
+    "spellcheck",
+    "style",
+    "tabindex",
+    "title",
+    "translate",
+    "writingsuggestions",
+    "xmlns",
+];
+
+/// https://html.spec.whatwg.org/multipage/webappapis.html#globaleventhandlers
+const EVENT_HANDLERS: &[&str] = &[
+    "onabort",
+    "onauxclick",
+    "onbeforeinput",
+    "onbeforematch",
+    "onbeforetoggle",
+    "onblur",
+    "oncancel",
+    "oncanplay",
+    "oncanplaythrough",
+    "onchange",
+    "onclick",
+    "onclose",
+    "oncontextlost",
+    "oncontextmenu",
+    "oncontextrestored",
+    "oncopy",
+    "oncuechange",
+    "oncut",
+    "ondblclick",
+    "ondrag",
+    "ondragend",
+    "ondragenter",
+    "ondragleave",
+    "ondragover",
+    "ondragstart",
+    "ondrop",
+    "ondurationchange",
+    "onemptied",
+    "onended",
+    "onerror",
+    "onfocus",
+    "onformdata",
+    "oninput",
+    "oninvalid",
+    "onkeydown",
+    "onkeypress",
+    "onkeyup",
+    "onload",
+    "onloadeddata",
+    "onloadedmetadata",
+    "onloadstart",
+    "onmousedown",
+    "onmouseenter",
+    "onmouseleave",
+    "onmousemove",
+    "onmouseout",
+    "onmouseover",
+    "onmouseup",
+    "onpaste",
+    "onpause",
+    "onplay",
+    "onplaying",
+    "onpointercancel",
+    "onpointerdown",
+    "onpointerenter",
+    "onpointerleave",
+    "onpointermove",
+    "onpointerout",
+    "onpointerover",
+    "onpointerup",
+    "onprogress",
+    "onratechange",
+    "onreset",
+    "onresize",
+    "onscroll",
+    "onscrollend",
+    "onsecuritypolicyviolation",
+    "onseeked",
+    "onseeking",
+    "onselect",
+    "onslotchange",
+    "onstalled",
+    "onsubmit",
+    "onsuspend",
+    "ontimeupdate",
+    "ontoggle",
+    "ontouchcancel",
+    "ontouchend",
+    "ontouchmove",
+    "ontouchstart",
+    "onvolumechange",
+    "onwaiting",
+    "onwheel",
+];
+
+/// RDFa attributes like `property` for Open Graph `<meta>` tags can be used on every element.
+/// https://www.w3.org/TR/html-rdfa/#extensions-to-the-html5-syntax
+const RDFA_ATTRIBUTES: &[&str] = &[
+    "about", "content", "datatype", "inlist", "prefix", "property", "rel", "resource", "rev",
+    "typeof", "vocab",
+];
+
+/// Obsolete presentational attributes that browsers still support and that are common in HTML emails.
+/// https://html.spec.whatwg.org/multipage/obsolete.html#non-conforming-features
+const OBSOLETE_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["charset", "coords", "name", "rev", "shape"]),
+    ("area", &["nohref"]),
+    (
+        "body",
+        &[
+            "alink",
+            "background",
+            "bgcolor",
+            "link",
+            "marginheight",
+            "marginwidth",
+            "text",
+            "vlink",
+        ],
+    ),
+    ("br", &["clear"]),
+    ("caption", &["align"]),
+    ("col", &["align", "char", "charoff", "valign", "width"]),
+    ("colgroup", &["align", "char", "charoff", "valign", "width"]),
+    ("div", &["align"]),
+    ("dl", &["compact"]),
+    ("h1", &["align"]),
+    ("h2", &["align"]),
+    ("h3", &["align"]),
+    ("h4", &["align"]),
+    ("h5", &["align"]),
+    ("h6", &["align"]),
+    ("hr", &["align", "color", "noshade", "size", "width"]),
+    (
+        "iframe",
+        &[
+            "align",
+            "frameborder",
+            "hspace",
+            "marginheight",
+            "marginwidth",
+            "scrolling",
+            "vspace",
+        ],
+    ),
+    (
+        "img",
+        &["align", "border", "hspace", "longdesc", "name", "vspace"],
+    ),
+    ("input", &["align", "hspace", "vspace"]),
+    ("legend", &["align"]),
+    ("li", &["type"]),
+    ("link", &["charset", "rev", "target"]),
+    ("meta", &["scheme"]),
+    ("ol", &["compact"]),
+    ("p", &["align"]),
+    ("pre", &["width"]),
+    ("script", &["charset", "language"]),
+    (
+        "table",
+        &[
+            "align",
+            "bgcolor",
+            "border",
+            "cellpadding",
+            "cellspacing",
+            "frame",
+            "height",
+            "rules",
+            "summary",
+            "width",
+        ],
+    ),
+    ("tbody", &["align", "char", "charoff", "valign"]),
+    (
+        "td",
+        &[
+            "abbr", "align", "axis", "bgcolor", "char", "charoff", "height", "nowrap", "scope",
+            "valign", "width",
+        ],
+    ),
+    ("tfoot", &["align", "char", "charoff", "valign"]),
+    (
+        "th",
+        &[
+            "align", "axis", "bgcolor", "char", "charoff", "height", "nowrap", "valign", "width",
+        ],
+    ),
+    ("thead", &["align", "char", "charoff", "valign"]),
+    (
+        "tr",
+        &["align", "bgcolor", "char", "charoff", "height", "valign"],
+    ),
+    ("ul", &["compact", "type"]),
+];
+
+/// The attributes of each element in addition to the global ones.
+/// https://html.spec.whatwg.org/multipage/indices.html#attributes-3
+const ELEMENT_ATTRIBUTES: &[(&str, &[&str])] = &[
+    (
+        "a",
+        &[
+            "download",
+            "href",
+            "hreflang",
+            "ping",
+            "referrerpolicy",
+            "rel",
+            "target",
+            "type",
+        ],
+    ),
+    ("abbr", &[]),
+    ("address", &[]),
+    (
+        "area",
+        &[
+            "alt",
+            "coords",
+            "download",
+            "href",
+            "ping",
+            "referrerpolicy",
+            "rel",
+            "shape",
+            "target",
+        ],
+    ),
+    ("article", &[]),
+    ("aside", &[]),
+    (
+        "audio",
+        &[
+            "autoplay",
+            "controls",
+            "crossorigin",
+            "loop",
+            "muted",
+            "preload",
+            "src",
+        ],
+    ),
+    ("b", &[]),
+    ("base", &["href", "target"]),
+    ("bdi", &[]),
+    ("bdo", &[]),
+    ("blockquote", &["cite"]),
+    (
+        "body",
+        &[
+            "onafterprint",
+            "onbeforeprint",
+            "onbeforeunload",
+            "onhashchange",
+            "onlanguagechange",
+            "onmessage",
+            "onmessageerror",
+            "onoffline",
+            "ononline",
+            "onpagehide",
+            "onpagereveal",
+            "onpageshow",
+            "onpageswap",
+            "onpopstate",
+            "onrejectionhandled",
+            "onstorage",
+            "onunhandledrejection",
+            "onunload",
+        ],
+    ),
+    ("br", &[]),
+    (
+        "button",
+        &[
+            "command",
+            "commandfor",
+            "disabled",
+            "form",
+            "formaction",
+            "formenctype",
+            "formmethod",
+            "formnovalidate",
+            "formtarget",
+            "name",
+            "popovertarget",
+            "popovertargetaction",
+            "type",
+            "value",
+        ],
+    ),
+    ("canvas", &["height", "width"]),
+    ("caption", &[]),
+    ("cite", &[]),
+    ("code", &[]),
+    ("col", &["span"]),
+    ("colgroup", &["span"]),
+    ("data", &["value"]),
+    ("datalist", &[]),
+    ("dd", &[]),
+    ("del", &["cite", "datetime"]),
+    ("details", &["name", "open"]),
+    ("dfn", &[]),
+    ("dialog", &["closedby", "open"]),
+    ("div", &[]),
+    ("dl", &[]),
+    ("dt", &[]),
+    ("em", &[]),
+    ("embed", &["height", "src", "type", "width"]),
+    ("fieldset", &["disabled", "form", "name"]),
+    ("figcaption", &[]),
+    ("figure", &[]),
+    ("footer", &[]),
+    (
+        "form",
+        &[
+            "accept-charset",
+            "action",
+            "autocomplete",
+            "enctype",
+            "method",
+            "name",
+            "novalidate",
+            "rel",
+            "target",
+        ],
+    ),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("head", &[]),
+    ("header", &[]),
+    ("hgroup", &[]),
+    ("hr", &[]),
+    ("html", &[]),
+    ("i", &[]),
+    (
+        "iframe",
+        &[
+            "allow",
+            "allowfullscreen",
+            "height",
+            "loading",
+            "name",
+            "referrerpolicy",
+            "sandbox",
+            "src",
+            "srcdoc",
+            "width",
+        ],
+    ),
+    (
+        "img",
+        &[
+            "alt",
+            "crossorigin",
+            "decoding",
+            "fetchpriority",
+            "height",
+            "ismap",
+            "loading",
+            "referrerpolicy",
+            "sizes",
+            "src",
+            "srcset",
+            "usemap",
+            "width",
+        ],
+    ),
+    (
+        "input",
+        &[
+            "accept",
+            "alpha",
+            "alt",
+            "autocomplete",
+            "checked",
+            "colorspace",
+            "dirname",
+            "disabled",
+            "form",
+            "formaction",
+            "formenctype",
+            "formmethod",
+            "formnovalidate",
+            "formtarget",
+            "height",
+            "list",
+            "max",
+            "maxlength",
+            "min",
+            "minlength",
+            "multiple",
+            "name",
+            "pattern",
+            "placeholder",
+            "popovertarget",
+            "popovertargetaction",
+            "readonly",
+            "required",
+            "size",
+            "src",
+            "step",
+            "type",
+            "value",
+            "width",
+        ],
+    ),
+    ("ins", &["cite", "datetime"]),
+    ("kbd", &[]),
+    ("label", &["for"]),
+    ("legend", &[]),
+    ("li", &["value"]),
+    (
+        "link",
+        &[
+            "as",
+            "blocking",
+            "color",
+            "crossorigin",
+            "disabled",
+            "fetchpriority",
+            "href",
+            "hreflang",
+            "imagesizes",
+            "imagesrcset",
+            "integrity",
+            "media",
+            "referrerpolicy",
+            "rel",
+            "sizes",
+            "type",
+        ],
+    ),
+    ("main", &[]),
+    ("map", &["name"]),
+    ("mark", &[]),
+    ("menu", &[]),
+    (
+        "meta",
+        &["charset", "content", "http-equiv", "media", "name"],
+    ),
+    ("meter", &["high", "low", "max", "min", "optimum", "value"]),
+    ("nav", &[]),
+    ("noscript", &[]),
+    (
+        "object",
+        &["data", "form", "height", "name", "type", "width"],
+    ),
+    ("ol", &["reversed", "start", "type"]),
+    ("optgroup", &["disabled", "label"]),
+    ("option", &["disabled", "label", "selected", "value"]),
+    ("output", &["for", "form", "name"]),
+    ("p", &[]),
+    ("picture", &[]),
+    ("pre", &[]),
+    ("progress", &["max", "value"]),
+    ("q", &["cite"]),
+    ("rp", &[]),
+    ("rt", &[]),
+    ("ruby", &[]),
+    ("s", &[]),
+    ("samp", &[]),
+    (
+        "script",
+        &[
+            "async",
+            "blocking",
+            "crossorigin",
+            "defer",
+            "fetchpriority",
+            "integrity",
+            "nomodule",
+            "referrerpolicy",
+            "src",
+            "type",
+        ],
+    ),
+    ("search", &[]),
+    ("section", &[]),
+    (
+        "select",
+        &[
+            "autocomplete",
+            "disabled",
+            "form",
+            "multiple",
+            "name",
+            "required",
+            "size",
+        ],
+    ),
+    ("slot", &["name"]),
+    ("small", &[]),
+    (
+        "source",
+        &["height", "media", "sizes", "src", "srcset", "type", "width"],
+    ),
+    ("span", &[]),
+    ("strong", &[]),
+    ("style", &["blocking", "media"]),
+    ("sub", &[]),
+    ("summary", &[]),
+    ("sup", &[]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["colspan", "headers", "rowspan"]),
+    (
+        "template",
+        &[
+            "shadowrootclonable",
+            "shadowrootdelegatesfocus",
+            "shadowrootmode",
+            "shadowrootserializable",
+        ],
+    ),
+    (
+        "textarea",
+        &[
+            "autocomplete",
+            "cols",
+            "dirname",
+            "disabled",
+            "form",
+            "maxlength",
+            "minlength",
+            "name",
+            "placeholder",
+            "readonly",
+            "required",
+            "rows",
+            "wrap",
+        ],
+    ),
+    ("tfoot", &[]),
+    ("th", &["abbr", "colspan", "headers", "rowspan", "scope"]),
+    ("thead", &[]),
+    ("time", &["datetime"]),
+    ("title", &[]),
+    ("tr", &[]),
+    ("track", &["default", "kind", "label", "src", "srclang"]),
+    ("u", &[]),
+    ("ul", &[]),
+    ("var", &[]),
+    (
+        "video",
+        &[
+            "autoplay",
+            "controls",
+            "crossorigin",
+            "height",
+            "loop",
+            "muted",
+            "playsinline",
+            "poster",
+            "preload",
+            "src",
+            "width",
+        ],
+    ),
+    ("wbr", &[]),
+];
+
+/// Checks that the direct children of `parent` are allowed by its content model.
+pub fn check_content_model(
+    parent: &HtmlTag,
+    children: &[HtmlInElementContext],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let parent_name = parent.to_string().to_ascii_lowercase();
+    for_each_child_element(children, &mut |child| {
+        let child_name = child.open_tag_name.to_string().to_ascii_lowercase();
+        if let Some(error) = content_model_error(&parent_name, &child_name) {
+            diagnostics.push(
+                child
+                    .open_tag_name
+                    .span()
+                    .error(error)
+                    .span_note(parent.span(), format!("<{parent_name}> opened here")),
+            );
+        }
+    });
+}
+
+/// Checks the attribute names of `element` against the attributes the HTML spec defines for it.
+///
+/// Names with a `-` like `data-*`, `aria-*` or `hx-*` are always allowed, custom elements and unknown elements aren't checked.
+/// Any other attribute can be allowed by writing it as a raw identifier like `r#foo="bar"`.
+pub fn check_attributes(
+    element: &HtmlTag,
+    attributes: &[HtmlInAttributeContext],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let element_name = element.to_string().to_ascii_lowercase();
+    let Some((_, element_attributes)) = ELEMENT_ATTRIBUTES
+        .iter()
+        .find(|(name, _)| *name == element_name)
+    else {
+        return;
+    };
+    let obsolete_attributes = OBSOLETE_ATTRIBUTES
+        .iter()
+        .find(|(name, _)| *name == element_name)
+        .map_or(&[][..], |(_, attributes)| attributes);
+    let allowed = || {
+        GLOBAL_ATTRIBUTES
+            .iter()
+            .chain(EVENT_HANDLERS)
+            .chain(RDFA_ATTRIBUTES)
+            .chain(element_attributes.iter())
+            .chain(obsolete_attributes)
+    };
+    for_each_attribute(attributes, &mut |name| {
+        let name_text = name_to_string(name).to_ascii_lowercase();
+        // names with a dash are `data-*`, `aria-*` or attributes of frameworks like `hx-get` and aren't checked
+        if name_text.contains('-')
+            || is_raw(name)
+            || name_text.contains(':')
+            || allowed().any(|allowed| *allowed == name_text)
+        {
+            return;
+        }
+        let suggestion = allowed()
+            .map(|allowed| (edit_distance(allowed, &name_text), allowed))
+            .filter(|(distance, _)| *distance <= if name_text.len() < 4 { 1 } else { 2 })
+            .min();
+        let diagnostic =
+            name_span(name).error(format!("unknown attribute {name_text} on <{element_name}>"));
+        let diagnostic = match suggestion {
+            Some((_, suggestion)) => diagnostic.help(format!("did you mean {suggestion}?")),
+            None => diagnostic.help(
+                "custom attributes need a - in their name like data-* or can be written as r#name",
+            ),
+        };
+        diagnostics
+            .push(diagnostic.span_note(element.span(), format!("<{element_name}> opened here")));
+    });
+}
+
+fn is_raw(name: &Punctuated<Ident, DashOrColon>) -> bool {
+    name.first()
+        .is_some_and(|ident| ident.to_string().starts_with("r#"))
+}
+
+// optimal string alignment distance so swapped letters like in `tpye` only count once
+fn edit_distance(left: &str, right: &str) -> usize {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+    let mut distances = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            distances[i][j] = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                distances[i][j] = distances[i][j].min(distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    distances[left.len()][right.len()]
+}
+
+// only known HTML parents are checked, unknown parents are usually SVG or MathML elements and `<template>` can contain anything
+fn content_model_error(parent: &str, child: &str) -> Option<String> {
+    if parent == "template"
+        || !ELEMENT_ATTRIBUTES.iter().any(|(name, _)| *name == parent)
+        || [parent, child]
+            .iter()
+            .any(|name| name.contains('-') || name.contains(':'))
+    {
+        return None;
+    }
+    if let Some((_, parents)) = PARENTS.iter().find(|(name, _)| *name == child) {
+        if !parents.contains(&parent) {
+            return Some(if child == "tr" && parent == "table" {
+                "<tr> is not allowed directly in <table>, the browser inserts a <tbody> around it"
+                    .to_owned()
+            } else {
+                format!(
+                    "<{child}> is only allowed in {}",
+                    parents
+                        .iter()
+                        .map(|parent| format!("<{parent}>"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            });
+        }
+        return None;
+    }
+    if PHRASING_PARENTS.contains(&parent) && FLOW_ONLY.contains(&child) {
+        return Some(format!(
+            "<{child}> is not allowed in <{parent}> because <{parent}> can only contain phrasing content"
+        ));
+    }
+    if parent == "head" && !METADATA.contains(&child) {
+        return Some(format!(
+            "<{child}> is not allowed in <head> because <head> can only contain metadata content"
+        ));
+    }
+    None
+}
+
+// children in control flow are children of the element as well
+fn for_each_child_element<'a>(
+    children: &'a [HtmlInElementContext],
+    fun: &mut impl FnMut(&'a HtmlElement),
+) {
+    for child in children {
+        match child {
+            HtmlInElementContext::Element(element) => fun(element),
+            HtmlInElementContext::If(html_if) => for_each_child_element_if(html_if, fun),
+            HtmlInElementContext::For(html_for) => for_each_child_element(&html_for.body.1, fun),
+            HtmlInElementContext::While(html_while) => {
+                for_each_child_element(&html_while.body.1, fun)
+            }
+            HtmlInElementContext::Match(html_match) => {
+                for arm in &html_match.arms {
+                    for_each_child_element(&arm.body.1, fun);
+                }
+            }
+            HtmlInElementContext::Literal(_)
+            | HtmlInElementContext::Computation(_)
+            | HtmlInElementContext::ComputedValue(_)
+            | HtmlInElementContext::Let(_)
+            | HtmlInElementContext::Comment(_) => {}
+        }
+    }
+}
+
+fn for_each_child_element_if<'a>(
+    html_if: &'a HtmlIf<Vec<HtmlInElementContext>>,
+    fun: &mut impl FnMut(&'a HtmlElement),
+) {
+    for_each_child_element(&html_if.then_branch.1, fun);
+    match &html_if.else_branch {
+        Some((_, HtmlElse::If(html_if))) => for_each_child_element_if(html_if, fun),
+        Some((_, HtmlElse::Else(_, inner))) => for_each_child_element(inner, fun),
+        None => {}
+    }
+}
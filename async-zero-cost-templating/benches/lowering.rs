@@ -0,0 +1,117 @@
+//! Compares the single-slot lowering of `html!` with the previous lowering that used a `tokio::sync::mpsc` channel.
+//!
+//! Run with `cargo bench --bench lowering`.
+
+extern crate alloc;
+
+use std::{
+    borrow::Cow,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use async_zero_cost_templating::html;
+use futures_core::{Future, Stream};
+use futures_util::StreamExt as _;
+use pin_project::pin_project;
+
+const ROWS: usize = 100;
+const ITERATIONS: u32 = 10_000;
+
+/// The stream the macro generated before, kept here as the baseline.
+#[pin_project]
+struct ChannelTemplateToStream<T, F: Future<Output = ()>> {
+    #[pin]
+    future: Option<F>,
+    receiver: tokio::sync::mpsc::Receiver<T>,
+}
+
+impl<T, F: Future<Output = ()>> Stream for ChannelTemplateToStream<T, F> {
+    type Item = T;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        match this.future.as_mut().as_pin_mut() {
+            Some(future) => match future.poll(cx) {
+                std::task::Poll::Ready(()) => {
+                    this.future.set(None);
+                    this.receiver.poll_recv(cx)
+                }
+                std::task::Poll::Pending => this.receiver.poll_recv(cx),
+            },
+            None => std::task::Poll::Ready(None),
+        }
+    }
+}
+
+// what `html! { <ul> for row in rows { <li>(row)</li> } </ul> }` expanded to
+fn channel_template<'a>(rows: &'a [String]) -> impl Stream<Item = Cow<'a, str>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    let future = async move {
+        tx.send(Cow::Borrowed("<ul>")).await.unwrap();
+        for row in rows {
+            tx.send(Cow::Borrowed("<li>")).await.unwrap();
+            tx.send(async_zero_cost_templating::escape_element_text(
+                Cow::Borrowed(row),
+            ))
+            .await
+            .unwrap();
+            tx.send(Cow::Borrowed("</li>")).await.unwrap();
+        }
+        tx.send(Cow::Borrowed("</ul>")).await.unwrap();
+    };
+    ChannelTemplateToStream {
+        future: Some(future),
+        receiver: rx,
+    }
+}
+
+fn slot_template<'a>(rows: &'a [String]) -> impl Stream<Item = Cow<'a, str>> {
+    html! {
+        <ul>
+            for row in rows {
+                <li>(Cow::Borrowed(row.as_str()))</li>
+            }
+        </ul>
+    }
+}
+
+fn bench<S: Stream<Item = Cow<'static, str>>>(
+    runtime: &tokio::runtime::Runtime,
+    name: &str,
+    template: impl Fn() -> S,
+) -> Duration {
+    let start = Instant::now();
+    runtime.block_on(async {
+        for _ in 0..ITERATIONS {
+            let result: String = template().collect().await;
+            black_box(result);
+        }
+    });
+    let elapsed = start.elapsed();
+    println!("{name:>8}: {:?} per template", elapsed / ITERATIONS);
+    elapsed
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let rows: &'static [String] = Vec::leak((0..ROWS).map(|row| row.to_string()).collect());
+
+    assert_eq!(
+        runtime.block_on(channel_template(rows).collect::<String>()),
+        runtime.block_on(slot_template(rows).collect::<String>())
+    );
+
+    let channel = bench(&runtime, "channel", || channel_template(rows));
+    let slot = bench(&runtime, "slot", || slot_template(rows));
+    println!(
+        "   ratio: {:.2}",
+        channel.as_secs_f64() / slot.as_secs_f64()
+    );
+}
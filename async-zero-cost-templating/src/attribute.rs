@@ -0,0 +1,54 @@
+use alloc::borrow::Cow;
+
+use crate::Escape;
+
+/// Checks that a dynamic attribute name of `..(attributes)` can't break out of the start tag.
+///
+/// Returns `None` if the name is empty or contains whitespace, control characters, noncharacters or one of `"'<>/=`.
+/// The pair is then skipped so one bad key doesn't abort a response that is already partially sent.
+pub fn attribute_name<'a>(name: impl Into<Cow<'a, str>>) -> Option<Cow<'a, str>> {
+    let name = name.into();
+    is_valid_attribute_name(&name).then_some(name)
+}
+
+// https://html.spec.whatwg.org/multipage/syntax.html#attributes-2
+fn is_valid_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|character| {
+            !character.is_whitespace()
+                && !character.is_control()
+                && !matches!(character, '"' | '\'' | '<' | '>' | '/' | '=')
+                && !is_noncharacter(character)
+        })
+}
+
+fn is_noncharacter(character: char) -> bool {
+    let character = u32::from(character);
+    (0xFDD0..=0xFDEF).contains(&character) || character & 0xFFFE == 0xFFFE
+}
+
+/// A value for `name?=(value)` that decides whether the attribute is emitted at all.
+///
+/// `false` and `None` omit the attribute, `true` emits just the name and `Some(value)` emits the escaped value.
+pub trait OptionalAttribute<'a> {
+    type Value: Escape<'a>;
+
+    /// `None` omits the attribute and `Some(None)` emits it without a value.
+    fn into_attribute(self) -> Option<Option<Self::Value>>;
+}
+
+impl<'a> OptionalAttribute<'a> for bool {
+    type Value = Cow<'a, str>;
+
+    fn into_attribute(self) -> Option<Option<Self::Value>> {
+        self.then_some(None)
+    }
+}
+
+impl<'a, T: Escape<'a>> OptionalAttribute<'a> for Option<T> {
+    type Value = T;
+
+    fn into_attribute(self) -> Option<Option<Self::Value>> {
+        self.map(Some)
+    }
+}
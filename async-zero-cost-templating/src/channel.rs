@@ -0,0 +1,81 @@
+use alloc::sync::Arc;
+use core::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use std::sync::{Mutex, PoisonError};
+
+/// Creates the single-slot handoff between a template future and its [`crate::TemplateToStream`].
+///
+/// There is no buffering, the future stores one value and then yields so the stream can take it out.
+/// The slot is a mutex so templates are `Send` and can be used as a response body on a multi-threaded runtime,
+/// it is never contended because the future and the stream are polled together.
+///
+/// The future holds a `&Sender` across every await, so it is only `Send` if the slot is `Sync`, and without `unsafe`
+/// that needs a lock. This costs one allocation per template and an uncontended lock per piece. For a 100 row list
+/// `benches/lowering.rs` measured about 18µs per template, an `Rc<RefCell<_>>` slot that isn't `Send` took about 9µs
+/// and the previous `tokio::sync::mpsc` channel about 80µs.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let slot = Arc::new(Mutex::new(None));
+    (Sender(slot.clone()), Receiver(slot))
+}
+
+// the future and the receiver are owned by the same `TemplateToStream`, so dropping the stream also stops the template
+pub struct Sender<T>(Arc<Mutex<Option<T>>>);
+
+pub struct Receiver<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> Sender<T> {
+    /// Hands `value` to the stream and waits until the stream returned it.
+    pub async fn send(&self, value: T) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(value);
+        YieldOnce(false).await
+    }
+
+    /// Forwards all values of a nested template.
+    pub async fn send_all(&self, stream: impl Stream<Item = T>) {
+        let mut stream = pin!(stream);
+        while let Some(value) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            self.send(value).await;
+        }
+    }
+
+    /// Forwards all values of a nested fallible template and stops at the first error.
+    pub async fn try_send_all<E>(&self, stream: impl Stream<Item = Result<T, E>>) -> Result<(), E> {
+        let mut stream = pin!(stream);
+        while let Some(value) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            self.send(value?).await;
+        }
+        Ok(())
+    }
+}
+
+/// Waits for the next value of a pinned stream, `for await` loops call this.
+pub async fn next<S: Stream + ?Sized>(mut stream: Pin<&mut S>) -> Option<S::Item> {
+    poll_fn(|cx| stream.as_mut().poll_next(cx)).await
+}
+
+impl<T> Receiver<T> {
+    pub fn take(&self) -> Option<T> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).take()
+    }
+}
+
+// this doesn't wake because the stream returns the value and therefore is polled again anyways
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+}
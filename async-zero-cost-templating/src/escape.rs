@@ -0,0 +1,187 @@
+use alloc::borrow::Cow;
+
+use crate::Render;
+
+/// Marks a value as trusted markup that is inserted without escaping.
+///
+/// Only wrap values that can't contain user input, otherwise this allows cross-site scripting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PreEscaped<T>(pub T);
+
+/// Escapes a computed value `( value )` for the context it is inserted into.
+///
+/// The generated code calls this for every computed value, so the escaping can't be forgotten.
+pub trait Escape<'a> {
+    /// Escapes the value for the text content of an element.
+    fn escape_element_text(self) -> Cow<'a, str>;
+
+    /// Escapes the value for the inside of a double quoted attribute value.
+    fn escape_attribute_value(self) -> Cow<'a, str>;
+
+    /// Converts the value to a JavaScript string literal inside of `<script>`.
+    fn escape_script(self) -> Cow<'a, str>;
+
+    /// Escapes the value for a CSS value inside of `<style>`.
+    fn escape_style(self) -> Cow<'a, str>;
+
+    /// Escapes the value for the inside of a `<!-- comment -->`.
+    fn escape_comment(self) -> Cow<'a, str>;
+}
+
+impl<'a, T: Render<'a>> Escape<'a> for T {
+    fn escape_element_text(self) -> Cow<'a, str> {
+        escape_element_text(self.render())
+    }
+
+    fn escape_attribute_value(self) -> Cow<'a, str> {
+        escape_attribute_value(self.render())
+    }
+
+    fn escape_script(self) -> Cow<'a, str> {
+        escape_script(self.render())
+    }
+
+    fn escape_style(self) -> Cow<'a, str> {
+        escape_style(self.render())
+    }
+
+    fn escape_comment(self) -> Cow<'a, str> {
+        escape_comment(self.render())
+    }
+}
+
+impl<'a, T: Into<Cow<'a, str>>> Escape<'a> for PreEscaped<T> {
+    fn escape_element_text(self) -> Cow<'a, str> {
+        self.0.into()
+    }
+
+    fn escape_attribute_value(self) -> Cow<'a, str> {
+        self.0.into()
+    }
+
+    fn escape_script(self) -> Cow<'a, str> {
+        self.0.into()
+    }
+
+    fn escape_style(self) -> Cow<'a, str> {
+        self.0.into()
+    }
+
+    fn escape_comment(self) -> Cow<'a, str> {
+        self.0.into()
+    }
+}
+
+/// Escapes `&`, `<` and `>` so the value can't start a tag or a character reference.
+pub fn escape_element_text(input: Cow<'_, str>) -> Cow<'_, str> {
+    escape(input, |character| match character {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        _ => None,
+    })
+}
+
+/// Escapes `&`, `"`, `'`, `<` and `>` so the value can't end the attribute value.
+pub fn escape_attribute_value(input: Cow<'_, str>) -> Cow<'_, str> {
+    escape(input, |character| match character {
+        '&' => Some("&amp;"),
+        '"' => Some("&quot;"),
+        '\'' => Some("&#39;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        _ => None,
+    })
+}
+
+/// Converts the value to a quoted string literal that is valid JavaScript and JSON.
+///
+/// `<`, `>` and `&` are escaped as well so the value can't contain `</script>` or `<!--`.
+pub fn escape_script(input: Cow<'_, str>) -> Cow<'_, str> {
+    let mut output = String::with_capacity(input.len() + 2);
+    output.push('"');
+    for character in input.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '<' | '>' | '&' | '\'' | '\u{2028}' | '\u{2029}' => {
+                output.push_str(&format!("\\u{:04X}", character as u32))
+            }
+            character if character.is_control() => {
+                output.push_str(&format!("\\u{:04X}", character as u32))
+            }
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+    Cow::Owned(output)
+}
+
+/// Escapes everything except letters, digits and `#-.%_` with CSS escapes like `\3B `.
+///
+/// This keeps values like `#fff`, `10px` or `50%` working but prevents leaving the declaration.
+pub fn escape_style(input: Cow<'_, str>) -> Cow<'_, str> {
+    let needs_escape = |character: char| {
+        character.is_ascii() && !character.is_ascii_alphanumeric() && !"#-.%_".contains(character)
+    };
+    if !input.contains(needs_escape) {
+        return input;
+    }
+    let mut output = String::with_capacity(input.len() + 8);
+    for character in input.chars() {
+        if needs_escape(character) {
+            output.push_str(&format!("\\{:X} ", character as u32));
+        } else {
+            output.push(character);
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// Separates `--` and `<!-` with a space and adds a space after a trailing `-` and before a leading `>` or `->`.
+///
+/// Comments can't contain entities, so this keeps the value from ending the comment even if it is
+/// directly followed by another value or the closing `-->`.
+pub fn escape_comment(input: Cow<'_, str>) -> Cow<'_, str> {
+    if !input.contains("--")
+        && !input.contains("<!-")
+        && !input.ends_with('-')
+        && !input.starts_with('>')
+        && !input.starts_with("->")
+    {
+        return input;
+    }
+    let mut output = String::with_capacity(input.len() + 4);
+    if input.starts_with('>') || input.starts_with("->") {
+        output.push(' ');
+    }
+    for character in input.chars() {
+        if character == '-' && (output.ends_with('-') || output.ends_with("<!")) {
+            output.push(' ');
+        }
+        output.push(character);
+    }
+    if output.ends_with('-') {
+        output.push(' ');
+    }
+    Cow::Owned(output)
+}
+
+// only allocates if there is something to escape so borrowed values without special characters stay borrowed
+fn escape(input: Cow<'_, str>, replacement: impl Fn(char) -> Option<&'static str>) -> Cow<'_, str> {
+    let Some(first) = input.find(|character| replacement(character).is_some()) else {
+        return input;
+    };
+    let mut output = String::with_capacity(input.len() + 8);
+    output.push_str(&input[..first]);
+    for character in input[first..].chars() {
+        match replacement(character) {
+            Some(replacement) => output.push_str(replacement),
+            None => output.push(character),
+        }
+    }
+    Cow::Owned(output)
+}
@@ -1,16 +1,41 @@
 extern crate alloc;
 
-pub use async_zero_cost_templating_proc_macro::html;
+mod attribute;
+mod channel;
+mod escape;
+mod render;
+#[cfg(feature = "tokio")]
+mod tokio_io;
+
+pub use async_zero_cost_templating_proc_macro::{
+    html, html_attribute_value, html_attributes, try_html, try_html_attribute_value,
+    try_html_attributes,
+};
+pub use attribute::{attribute_name, OptionalAttribute};
+#[doc(hidden)]
+pub use channel::{channel, next, Receiver, Sender};
+pub use escape::{
+    escape_attribute_value, escape_comment, escape_element_text, escape_script, escape_style,
+    Escape, PreEscaped,
+};
+pub use render::{Displayed, Render};
+#[cfg(feature = "tokio")]
+pub use tokio_io::write_to;
 use pin_project::pin_project;
-use std::convert::Infallible;
+use std::{
+    convert::{identity, Infallible},
+    marker::PhantomData,
+};
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures_core::{Future, Stream};
+use futures_util::{StreamExt as _, TryStreamExt as _};
 
-use http_body::{Body, Frame};
+use http_body::{Body, Frame, SizeHint};
 
-// The reason we use a channel for now it that we want to be able to template values that don't have a lifetime of 'static and it seems like our Cell hack doesn't allow this because of invariance?
-// Because we also want to be able to send values with a lifetime of static depening on the use case (all returned values live forever).
+// We want to be able to template values that don't have a lifetime of 'static but also values with a lifetime of 'static depending on the use case (all returned values live forever).
+// This works with the Arc<Mutex<Option<T>>> slot in `channel` because T is inferred per template and a Cow<'static, str> coerces to the inferred Cow<'a, str> when it is sent.
+// The slot is Sync so templates are Send if everything they hold across an await is.
 
 // we don't want to use an unstable edition so we can't use `async gen`
 // we don't want to use unsafe so we can't use an async coroutine lowering
@@ -20,21 +45,148 @@ use http_body::{Body, Frame};
 
 // it should emit blocks of a specified size to reduce fragmentation. This means the goal is not always lowest latency but little overhead and then lowest latency
 
-pub struct TemplateHttpBody<S: Stream<Item = Bytes>> {
-    pub stream: S,
-    pub chunk_size: usize,
+#[pin_project]
+pub struct TemplateHttpBody<S> {
+    #[pin]
+    stream: Option<S>,
+    buffer: BytesMut,
+    chunk_size: usize,
+}
+
+impl<S> TemplateHttpBody<S> {
+    /// Collects the pieces of `stream` into frames of at least `chunk_size` bytes.
+    /// A smaller frame is only sent if the stream is pending or finished.
+    pub fn new(stream: S, chunk_size: usize) -> Self {
+        Self {
+            stream: Some(stream),
+            buffer: BytesMut::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
 }
 
-impl<S: Stream<Item = Bytes>> Body for TemplateHttpBody<S> {
-    type Data = &'static [u8];
+impl<S: Stream> Body for TemplateHttpBody<S>
+where
+    S::Item: AsRef<str>,
+{
+    type Data = Bytes;
 
     type Error = Infallible;
 
     fn poll_frame(
         self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
+        cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
-        todo!()
+        let this = self.project();
+        poll_frame(this.stream, this.buffer, *this.chunk_size, cx, Ok)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.stream.is_none() && self.buffer.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        size_hint(self.is_end_stream(), &self.buffer)
+    }
+}
+
+/// Like [`TemplateHttpBody`] but for fallible templates, an error aborts the body.
+#[pin_project]
+pub struct TryTemplateHttpBody<S> {
+    #[pin]
+    stream: Option<S>,
+    buffer: BytesMut,
+    chunk_size: usize,
+}
+
+impl<S> TryTemplateHttpBody<S> {
+    /// Collects the pieces of `stream` into frames of at least `chunk_size` bytes.
+    /// A smaller frame is only sent if the stream is pending or finished.
+    pub fn new(stream: S, chunk_size: usize) -> Self {
+        Self {
+            stream: Some(stream),
+            buffer: BytesMut::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+}
+
+impl<S: Stream<Item = Result<T, E>>, T: AsRef<str>, E> Body for TryTemplateHttpBody<S> {
+    type Data = Bytes;
+
+    type Error = E;
+
+    fn poll_frame(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        poll_frame(this.stream, this.buffer, *this.chunk_size, cx, identity)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.stream.is_none() && self.buffer.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        size_hint(self.is_end_stream(), &self.buffer)
+    }
+}
+
+// the buffered data is dropped on an error because the response is aborted anyways
+fn poll_frame<S: Stream, T: AsRef<str>, E>(
+    mut stream_option: std::pin::Pin<&mut Option<S>>,
+    buffer: &mut BytesMut,
+    chunk_size: usize,
+    cx: &mut std::task::Context<'_>,
+    into_result: impl Fn(S::Item) -> Result<T, E>,
+) -> std::task::Poll<Option<Result<Frame<Bytes>, E>>> {
+    loop {
+        let Some(stream) = stream_option.as_mut().as_pin_mut() else {
+            return std::task::Poll::Ready(None);
+        };
+        match stream.poll_next(cx) {
+            std::task::Poll::Ready(Some(value)) => match into_result(value) {
+                Ok(value) => {
+                    buffer.extend_from_slice(value.as_ref().as_bytes());
+                    if buffer.len() >= chunk_size {
+                        let frame = buffer.split().freeze();
+                        buffer.reserve(chunk_size);
+                        return std::task::Poll::Ready(Some(Ok(Frame::data(frame))));
+                    }
+                }
+                Err(error) => {
+                    stream_option.set(None);
+                    buffer.clear();
+                    return std::task::Poll::Ready(Some(Err(error)));
+                }
+            },
+            std::task::Poll::Ready(None) => {
+                stream_option.set(None);
+                if buffer.is_empty() {
+                    return std::task::Poll::Ready(None);
+                }
+                let frame = std::mem::take(buffer).freeze();
+                return std::task::Poll::Ready(Some(Ok(Frame::data(frame))));
+            }
+            // flush early so waiting for e.g. a database doesn't delay what we already have
+            std::task::Poll::Pending if !buffer.is_empty() => {
+                let frame = buffer.split().freeze();
+                buffer.reserve(chunk_size);
+                return std::task::Poll::Ready(Some(Ok(Frame::data(frame))));
+            }
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    }
+}
+
+fn size_hint(is_end_stream: bool, buffer: &BytesMut) -> SizeHint {
+    if is_end_stream {
+        SizeHint::with_exact(0)
+    } else {
+        let mut size_hint = SizeHint::new();
+        size_hint.set_lower(buffer.len() as u64);
+        size_hint
     }
 }
 
@@ -43,27 +195,46 @@ fn ui() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/ui/compile_fail/*.rs");
     t.pass("tests/ui/pass/*.rs");
+    #[cfg(feature = "validation")]
+    {
+        t.compile_fail("tests/ui/validation/*.rs");
+        t.pass("tests/ui/validation/pass/*.rs");
+    }
+}
+
+/// Marker types for what a template contains so it can only be inserted where that is valid.
+pub mod context {
+    /// Elements and text, created by `html!`.
+    pub enum Element {}
+
+    /// Attributes of an element, created by `html_attributes!`.
+    pub enum Attribute {}
+
+    /// The inside of an attribute value, created by `html_attribute_value!`.
+    pub enum AttributeValue {}
 }
 
 #[pin_project]
-pub struct TemplateToStream<T, F: Future<Output = ()> + Send> {
+pub struct TemplateToStream<T, F: Future<Output = ()>, C = context::Element> {
     #[pin]
     future: Option<F>,
-    // our Cell hack didn't work because of invariance? We want to be able to have a channel that sends non-'static values but also accepts static values.
-    // we could try Rc<T> or Rc<Cell<T>> because I think the problem was that we needed to pass around a &Cell<T<'lifetime>>
-    receiver: tokio::sync::mpsc::Receiver<T>,
+    receiver: Receiver<T>,
+    context: PhantomData<fn() -> C>,
 }
 
-impl<T, F: Future<Output = ()> + Send> TemplateToStream<T, F> {
-    pub fn new(future: F, receiver: tokio::sync::mpsc::Receiver<T>) -> Self {
+impl<T, F: Future<Output = ()>, C> TemplateToStream<T, F, C> {
+    // only the macros should create this, otherwise the context would not be checked
+    #[doc(hidden)]
+    pub fn new(future: F, receiver: Receiver<T>) -> Self {
         Self {
             future: Some(future),
             receiver,
+            context: PhantomData,
         }
     }
 }
 
-impl<T, F: Future<Output = ()> + Send> Stream for TemplateToStream<T, F> {
+impl<T, F: Future<Output = ()>, C> Stream for TemplateToStream<T, F, C> {
     type Item = T;
 
     fn poll_next(
@@ -76,11 +247,91 @@ impl<T, F: Future<Output = ()> + Send> Stream for TemplateToStream<T, F> {
             Some(future) => match future.poll(cx) {
                 std::task::Poll::Ready(()) => {
                     this.future.set(None);
-                    this.receiver.poll_recv(cx)
+                    std::task::Poll::Ready(this.receiver.take())
+                }
+                // if the future yielded because of a send the value is in the slot, otherwise it waits for something else and registered the waker for that
+                std::task::Poll::Pending => match this.receiver.take() {
+                    Some(value) => std::task::Poll::Ready(Some(value)),
+                    None => std::task::Poll::Pending,
+                },
+            },
+            None => std::task::Poll::Ready(None),
+        }
+    }
+}
+
+/// The stream of `try_html!` and its variants, it yields the error of the template and then ends.
+#[pin_project]
+pub struct TryTemplateToStream<T, E, F: Future<Output = Result<(), E>>, C = context::Element> {
+    #[pin]
+    future: Option<F>,
+    receiver: Receiver<T>,
+    context: PhantomData<fn() -> C>,
+}
+
+impl<T, E, F: Future<Output = Result<(), E>>, C> TryTemplateToStream<T, E, F, C> {
+    // only the macros should create this, otherwise the context would not be checked
+    #[doc(hidden)]
+    pub fn new(future: F, receiver: Receiver<T>) -> Self {
+        Self {
+            future: Some(future),
+            receiver,
+            context: PhantomData,
+        }
+    }
+}
+
+impl<T, E, F: Future<Output = Result<(), E>>, C> Stream for TryTemplateToStream<T, E, F, C> {
+    type Item = Result<T, E>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        match this.future.as_mut().as_pin_mut() {
+            Some(future) => match future.poll(cx) {
+                std::task::Poll::Ready(Ok(())) => {
+                    this.future.set(None);
+                    std::task::Poll::Ready(this.receiver.take().map(Ok))
+                }
+                std::task::Poll::Ready(Err(error)) => {
+                    this.future.set(None);
+                    std::task::Poll::Ready(Some(Err(error)))
                 }
-                std::task::Poll::Pending => this.receiver.poll_recv(cx),
+                std::task::Poll::Pending => match this.receiver.take() {
+                    Some(value) => std::task::Poll::Ready(Some(Ok(value))),
+                    None => std::task::Poll::Pending,
+                },
             },
             None => std::task::Poll::Ready(None),
         }
     }
 }
+
+/// Converts the templates that can be inserted with `{ }` into a fallible template with context `C`.
+#[doc(hidden)]
+pub trait IntoTryTemplate<C, T, E> {
+    type Stream: Stream<Item = Result<T, E>>;
+
+    fn into_try_template(self) -> Self::Stream;
+}
+
+impl<T, E, F: Future<Output = ()>, C> IntoTryTemplate<C, T, E> for TemplateToStream<T, F, C> {
+    type Stream = futures_util::stream::Map<Self, fn(T) -> Result<T, E>>;
+
+    fn into_try_template(self) -> Self::Stream {
+        self.map(Ok as fn(T) -> Result<T, E>)
+    }
+}
+
+impl<T, E: From<E2>, E2, F: Future<Output = Result<(), E2>>, C> IntoTryTemplate<C, T, E>
+    for TryTemplateToStream<T, E2, F, C>
+{
+    type Stream = futures_util::stream::MapErr<Self, fn(E2) -> E>;
+
+    fn into_try_template(self) -> Self::Stream {
+        self.map_err(E::from as fn(E2) -> E)
+    }
+}
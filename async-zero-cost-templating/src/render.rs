@@ -0,0 +1,87 @@
+use alloc::{borrow::Cow, string::String, sync::Arc};
+use core::fmt;
+
+/// Converts a value of `( value )` to text, the generated code escapes the text afterwards.
+///
+/// Implement this for your own types to use them directly in templates.
+pub trait Render<'a> {
+    fn render(self) -> Cow<'a, str>;
+}
+
+impl<'a> Render<'a> for Cow<'a, str> {
+    fn render(self) -> Cow<'a, str> {
+        self
+    }
+}
+
+impl<'a> Render<'a> for &'a str {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<'a> Render<'a> for &'a String {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<'a> Render<'a> for String {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Owned(self)
+    }
+}
+
+// the Arc can't be borrowed for 'a because it is moved into the call
+impl<'a> Render<'a> for Arc<str> {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Owned(String::from(&*self))
+    }
+}
+
+impl<'a> Render<'a> for &'a Arc<str> {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+/// `None` renders nothing.
+impl<'a, T: Render<'a>> Render<'a> for Option<T> {
+    fn render(self) -> Cow<'a, str> {
+        match self {
+            Some(value) => value.render(),
+            None => Cow::Borrowed(""),
+        }
+    }
+}
+
+macro_rules! render_to_string {
+    ($($t: ty),*) => {
+        $(
+            impl<'a> Render<'a> for $t {
+                fn render(self) -> Cow<'a, str> {
+                    Cow::Owned(self.to_string())
+                }
+            }
+
+            // for `(&count)` and `for value in &values`
+            impl<'a> Render<'a> for &$t {
+                fn render(self) -> Cow<'a, str> {
+                    Cow::Owned(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+render_to_string!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, char);
+
+/// Renders any [`fmt::Display`] value, e.g. a date from a library that doesn't implement [`Render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Displayed<T>(pub T);
+
+impl<'a, T: fmt::Display> Render<'a> for Displayed<T> {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Owned(self.0.to_string())
+    }
+}
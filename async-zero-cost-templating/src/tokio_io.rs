@@ -0,0 +1,21 @@
+use core::pin::pin;
+
+use futures_core::Stream;
+use futures_util::StreamExt as _;
+use tokio::io::{AsyncWrite, AsyncWriteExt as _};
+
+/// Writes all pieces of a template to `writer`, e.g. a `tokio::net::TcpStream`.
+///
+/// The pieces are written as they are produced, wrap the writer in a `tokio::io::BufWriter` to reduce the number of writes.
+pub async fn write_to<S, W>(stream: S, writer: &mut W) -> std::io::Result<()>
+where
+    S: Stream,
+    S::Item: AsRef<str>,
+    W: AsyncWrite + Unpin,
+{
+    let mut stream = pin!(stream);
+    while let Some(value) = stream.next().await {
+        writer.write_all(value.as_ref().as_bytes()).await?;
+    }
+    writer.flush().await
+}
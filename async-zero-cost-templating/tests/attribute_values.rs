@@ -0,0 +1,27 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, html_attribute_value};
+use futures_util::stream::StreamExt;
+
+#[tokio::test]
+async fn test() {
+    let url = "/search?q=a&b";
+    let stream = html! {
+        <a href=(url) title={ html_attribute_value! { "Search " (url) } }>"search"</a>
+        <input type="number" width=100 step=-0.5 min=-1 max=0x10 accesskey='"'>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<a href="/search?q=a&amp;b" title="Search /search?q=a&amp;b">search</a><input type="number" width="100" step="-0.5" min="-1" max="16" accesskey="&quot;">"#
+    )
+}
+
+#[tokio::test]
+async fn raw_identifier() {
+    let stream = html! {
+        <span r#frobnicate="yes"></span>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<span frobnicate="yes"></span>"#)
+}
@@ -0,0 +1,43 @@
+extern crate alloc;
+
+use std::{cell::Cell, pin::pin};
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+struct DropGuard<'a>(&'a Cell<bool>);
+
+impl Drop for DropGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+fn render_row(rendered: &Cell<u32>, row: u32) -> u32 {
+    rendered.set(rendered.get() + 1);
+    row
+}
+
+#[tokio::test]
+async fn drop_stream_mid_render() {
+    let rendered = Cell::new(0);
+    let dropped = Cell::new(false);
+    {
+        let rendered = &rendered;
+        let dropped = &dropped;
+        let stream = html! {
+            let _guard = DropGuard(dropped);
+            <ul>
+                for row in 0..100 {
+                    <li>(render_row(rendered, row))</li>
+                }
+            </ul>
+        };
+        let mut stream = pin!(stream);
+        assert_eq!(stream.next().await.unwrap(), "<ul>");
+        assert_eq!(stream.next().await.unwrap(), "<li>");
+        assert_eq!(stream.next().await.unwrap(), "0");
+    }
+    assert!(dropped.get());
+    assert_eq!(rendered.get(), 1);
+}
@@ -0,0 +1,66 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+use std::borrow::Cow;
+
+#[tokio::test]
+async fn test() {
+    let is_active = true;
+    let is_disabled = false;
+    let extra = Cow::Borrowed("btn-\"lg\"");
+    let stream = html! {
+        <button class=["btn", "btn-primary", "active" => is_active, "disabled" => is_disabled, (extra)]></button>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<button class="btn btn-primary active btn-&quot;lg&quot;"></button>"#
+    )
+}
+
+#[tokio::test]
+async fn leading_conditions() {
+    for (first, second, expected) in [
+        (false, false, r#"<a class="link"></a>"#),
+        (true, false, r#"<a class="first link"></a>"#),
+        (false, true, r#"<a class="second link"></a>"#),
+        (true, true, r#"<a class="first second link"></a>"#),
+    ] {
+        let stream = html! {
+            <a class=["first" => first, "second" => second, "link"]></a>
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+}
+
+#[tokio::test]
+async fn only_conditions() {
+    let stream = html! {
+        <a class=["first" => false, "second" => true, "third" => true]></a>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<a class="second third"></a>"#)
+}
+
+#[tokio::test]
+async fn empty_computed_classes() {
+    for (extra, expected) in [
+        ("", r#"<a class="a b"></a>"#),
+        ("c", r#"<a class="a c b"></a>"#),
+    ] {
+        let stream = html! {
+            <a class=["a", (Cow::Borrowed(extra)), "", "b"]></a>
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+    for (first, expected) in [("", r#"<a class="b"></a>"#), ("a", r#"<a class="a b"></a>"#)] {
+        let stream = html! {
+            <a class=[(Cow::Borrowed(first)), (Cow::Borrowed("")) => true, "b"]></a>
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+}
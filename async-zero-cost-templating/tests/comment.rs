@@ -0,0 +1,59 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+#[cfg(not(feature = "minify"))]
+#[tokio::test]
+async fn test() {
+    let build = "1--2-";
+    let attack = "-><script>alert(1)</script>";
+    let stream = html! {
+        <!-- "build " (build) -->
+        <p>"text"</p>
+        <!-- "a -- b" -->
+        <!-- (">") -->
+        <!-- r"-->" -->
+        <!-- (attack) -->
+        <!-- "->" -->
+        <!-- ("<!--") "<!-" -->
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        "<!--build 1- -2- --><p>text</p><!--a - - b--><!-- >--><!--- ->--><!-- -><script>alert(1)</script>--><!-- ->--><!--<! - - <! - -->"
+    )
+}
+
+#[tokio::test]
+async fn conditional() {
+    let stream = html! {
+        <!-- "[if mso]><table><tr><td><![endif]" -->
+        <p>"content"</p>
+        <!-- "[if mso]></td></tr></table><![endif]" -->
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        "<!--[if mso]><table><tr><td><![endif]--><p>content</p><!--[if mso]></td></tr></table><![endif]-->"
+    )
+}
+
+#[cfg(feature = "minify")]
+#[tokio::test]
+#[deny(unused_variables)]
+async fn minify() {
+    let cell = std::cell::Cell::new(false);
+    let evaluated = &cell;
+    let build = "1";
+    let stream = html! {
+        <!-- "only for developers" -->
+        <p>
+            <!-- "build " (build) (evaluated.set(true)) -->
+            "text"
+        </p>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, "<p>text</p>");
+    assert!(cell.get());
+}
@@ -0,0 +1,38 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{
+    context, html, html_attribute_value, html_attributes, TemplateToStream,
+};
+use futures_core::Future;
+use futures_util::StreamExt as _;
+use std::borrow::Cow;
+
+pub fn attributes<'a>(
+    value: &'a str,
+) -> TemplateToStream<Cow<'a, str>, impl Future<Output = ()> + 'a, context::Attribute> {
+    html_attributes! {
+        class="link" title=[(Cow::Borrowed(value))]
+    }
+}
+
+pub fn attribute_value<'a>(
+    value: &'a str,
+) -> TemplateToStream<Cow<'a, str>, impl Future<Output = ()> + 'a, context::AttributeValue> {
+    html_attribute_value! {
+        "/users/" (Cow::Borrowed(value))
+    }
+}
+
+#[tokio::test]
+async fn test() {
+    let value = String::from("a&b");
+    let value = &value;
+    let stream = html! {
+        <a href=[{ attribute_value(value) }] { attributes(value) }>"Link"</a>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<a href="/users/a&amp;b" class="link" title="a&amp;b">Link</a>"#
+    )
+}
@@ -0,0 +1,19 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+#[tokio::test]
+async fn test() {
+    let stream = html! {
+        <my-widget data-id="1">
+            <x-for>"label"</x-for>
+            <svg:rect></svg:rect>
+        </my-widget>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<my-widget data-id="1"><x-for>label</x-for><svg:rect></svg:rect></my-widget>"#
+    )
+}
@@ -0,0 +1,63 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+struct User {
+    name: &'static str,
+    admin: bool,
+}
+
+#[tokio::test]
+async fn test() {
+    for (count, expected) in [(0, "none"), (1, "one"), (2, "few"), (10, "many")] {
+        let stream = html! {
+            if count == 0 {
+                "none"
+            } else if count == 1 {
+                "one"
+            } else if count < 5 {
+                "few"
+            } else {
+                "many"
+            }
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+}
+
+#[tokio::test]
+async fn if_let() {
+    for (user, expected) in [
+        (
+            Some(User {
+                name: "alice",
+                admin: true,
+            }),
+            r#"<p class="admin">alice</p>"#,
+        ),
+        (
+            Some(User {
+                name: "bob",
+                admin: false,
+            }),
+            r#"<p>bob</p>"#,
+        ),
+        (None, r#"<p>anonymous</p>"#),
+    ] {
+        let stream = html! {
+            <p if let Some(User { admin: true, .. }) = &user {
+                class="admin"
+            }>
+                if let Some(User { name, .. }) = &user {
+                    (alloc::borrow::Cow::Borrowed(*name))
+                } else if let None = user {
+                    "anonymous"
+                }
+            </p>
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+}
@@ -0,0 +1,54 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, PreEscaped};
+use futures_util::stream::StreamExt;
+
+#[tokio::test]
+async fn test() {
+    let text = alloc::borrow::Cow::Borrowed("<script>alert(1)</script> & more");
+    let attribute = alloc::borrow::Cow::Borrowed(r#""><script>alert(1)</script>"#);
+    let stream = html! {
+        <a title=[(attribute)]>(text)</a>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<a title="&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;">&lt;script&gt;alert(1)&lt;/script&gt; &amp; more</a>"#
+    )
+}
+
+#[tokio::test]
+async fn pre_escaped() {
+    let markup = alloc::borrow::Cow::Borrowed("<b>trusted</b>");
+    let stream = html! {
+        <p>(PreEscaped(markup))</p>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<p><b>trusted</b></p>"#)
+}
+
+#[tokio::test]
+async fn literals() {
+    let stream = html! {
+        <p title="say \"hi\" & <bye>">"a < b & c > d" r"&nbsp;&copy;&#169;"</p>
+        <div class=["a&b", "c" => true]></div>
+        <script>"if (1 < 2 && true) {}"</script>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<p title="say &quot;hi&quot; &amp; &lt;bye&gt;">a &lt; b &amp; c &gt; d&nbsp;&copy;&#169;</p><div class="a&amp;b c"></div><script>if (1 < 2 && true) {}</script>"#
+    )
+}
+
+#[tokio::test]
+async fn raw_literals() {
+    let stream = html! {
+        <p title=r#"a" onclick="x"#>r#"<3 & &amp; &nbsp &copy;"#</p>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<p title="a&quot; onclick=&quot;x">&lt;3 &amp; &amp; &amp;nbsp &copy;</p>"#
+    )
+}
@@ -0,0 +1,44 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, TemplateToStream};
+use futures_core::Future;
+use futures_util::stream::StreamExt;
+use std::borrow::Cow;
+
+fn template<'a>(
+    title: &'a str,
+    rows: &'a [&'a str],
+) -> TemplateToStream<Cow<'a, str>, impl Future<Output = ()> + 'a> {
+    html! {
+        <h1>(Cow::Borrowed(title))</h1>
+        <ul>
+            for row in rows {
+                <li>(futures_util::future::ready(Cow::Borrowed(*row)).await)</li>
+            }
+        </ul>
+    }
+}
+
+const EXPECTED: &str = r#"<h1>Rows</h1><ul><li>a</li><li>b</li></ul>"#;
+
+#[tokio::test]
+async fn tokio() {
+    let result: String = template("Rows", &["a", "b"]).collect().await;
+    assert_eq!(result, EXPECTED)
+}
+
+#[test]
+fn futures_executor() {
+    let result: String = futures_executor::block_on(template("Rows", &["a", "b"]).collect());
+    assert_eq!(result, EXPECTED)
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn tokio_write_to() {
+    let mut output = Vec::new();
+    async_zero_cost_templating::write_to(template("Rows", &["a", "b"]), &mut output)
+        .await
+        .unwrap();
+    assert_eq!(output, EXPECTED.as_bytes())
+}
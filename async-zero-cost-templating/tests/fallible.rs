@@ -0,0 +1,106 @@
+extern crate alloc;
+
+use std::borrow::Cow;
+
+use async_zero_cost_templating::{html, try_html, TryTemplateHttpBody, TryTemplateToStream};
+use futures_core::Future;
+use futures_util::stream::StreamExt;
+use http_body::Body as _;
+use http_body_util::BodyExt as _;
+
+#[derive(Debug, PartialEq)]
+struct DatabaseError(&'static str);
+
+#[derive(Debug, PartialEq)]
+enum PageError {
+    Database(DatabaseError),
+}
+
+impl From<DatabaseError> for PageError {
+    fn from(error: DatabaseError) -> Self {
+        PageError::Database(error)
+    }
+}
+
+async fn query(id: u32) -> Result<&'static str, DatabaseError> {
+    match id {
+        1 => Ok("first"),
+        2 => Ok("second"),
+        _ => Err(DatabaseError("not found")),
+    }
+}
+
+fn rows(
+    ids: &[u32],
+) -> TryTemplateToStream<
+    Cow<'_, str>,
+    DatabaseError,
+    impl Future<Output = Result<(), DatabaseError>> + '_,
+> {
+    try_html! {
+        for id in ids {
+            <li>(query(*id).await?)</li>
+        }
+    }
+}
+
+fn page(
+    ids: &[u32],
+) -> TryTemplateToStream<Cow<'_, str>, PageError, impl Future<Output = Result<(), PageError>> + '_>
+{
+    let header = html! { <h1>"Rows"</h1> };
+    try_html! {
+        {header}
+        <ul>{rows(ids)}</ul>
+    }
+}
+
+#[tokio::test]
+async fn ok() {
+    let result: Result<Vec<_>, _> = page(&[1, 2])
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect();
+    assert_eq!(
+        result.unwrap().concat(),
+        "<h1>Rows</h1><ul><li>first</li><li>second</li></ul>"
+    )
+}
+
+#[tokio::test]
+async fn error() {
+    let result: Vec<_> = page(&[1, 3, 2]).collect().await;
+    let (error, rendered): (Vec<_>, Vec<_>) = result.into_iter().partition(Result::is_err);
+    assert_eq!(
+        rendered.into_iter().map(Result::unwrap).collect::<String>(),
+        "<h1>Rows</h1><ul><li>first</li><li>"
+    );
+    assert_eq!(
+        error
+            .into_iter()
+            .map(Result::unwrap_err)
+            .collect::<Vec<_>>(),
+        [PageError::Database(DatabaseError("not found"))]
+    );
+}
+
+#[tokio::test]
+async fn http_body() {
+    let mut body = std::pin::pin!(TryTemplateHttpBody::new(page(&[1, 3]), 8));
+    let mut frames = Vec::new();
+    let error = loop {
+        match body.frame().await.unwrap() {
+            Ok(frame) => frames.push(frame.into_data().unwrap()),
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error, PageError::Database(DatabaseError("not found")));
+    // what didn't fill a frame yet is dropped because the response is aborted
+    assert_eq!(
+        frames.concat(),
+        b"<h1>Rows</h1><ul><li>first</li>".as_slice()
+    );
+    assert!(body.frame().await.is_none());
+    assert!(body.is_end_stream());
+}
@@ -0,0 +1,43 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, html_attribute_value, html_attributes};
+use futures_util::stream::{self, StreamExt};
+
+#[tokio::test]
+async fn element() {
+    let rows = stream::iter([(1, "abc"), (2, "def")]).then(|row| async move { row });
+    let stream = html! {
+        <ul>
+            for await (id, name) in rows {
+                <li>(id) ": " (name)</li>
+            }
+        </ul>
+        for await letter in stream::iter(['x', 'y']) {
+            (letter)
+        }
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<ul><li>1: abc</li><li>2: def</li></ul>xy"#)
+}
+
+#[tokio::test]
+async fn attribute() {
+    let stream = html_attributes! {
+        for await attribute in stream::iter([("data-a", "1"), ("data-b", "2")]) {
+            ..([attribute])
+        }
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#" data-a="1" data-b="2""#)
+}
+
+#[tokio::test]
+async fn attribute_value() {
+    let stream = html_attribute_value! {
+        for await class in stream::iter(["a", "<b>"]) {
+            (class) " "
+        }
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"a &lt;b&gt; "#)
+}
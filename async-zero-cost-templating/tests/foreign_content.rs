@@ -0,0 +1,28 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+#[tokio::test]
+async fn test() {
+    let stream = html! {
+        <svg viewBox="0 0 10 10" xmlns:xlink="http://www.w3.org/1999/xlink">
+            <defs>
+                <linearGradient id="gradient" gradientUnits="userSpaceOnUse">
+                    <stop offset="0" />
+                </linearGradient>
+            </defs>
+            <use xlink:href="#icon" />
+            <foreignObject width="10" height="10">
+                <div>"html"</div>
+            </foreignObject>
+        </svg>
+        <math><mtext><b>"bold"</b></mtext></math>
+        <p lang="en" xml:lang="en">"text"</p>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r##"<svg viewBox="0 0 10 10" xmlns:xlink="http://www.w3.org/1999/xlink"><defs><linearGradient id="gradient" gradientUnits="userSpaceOnUse"><stop offset="0"/></linearGradient></defs><use xlink:href="#icon"/><foreignObject width="10" height="10"><div>html</div></foreignObject></svg><math><mtext><b>bold</b></mtext></math><p lang="en" xml:lang="en">text</p>"##
+    )
+}
@@ -0,0 +1,68 @@
+extern crate alloc;
+
+use std::task::Poll;
+
+use async_zero_cost_templating::{html, TemplateHttpBody};
+use http_body::Body as _;
+use http_body_util::BodyExt as _;
+
+#[tokio::test]
+async fn test() {
+    let stream = html! {
+        <h1>"Hello"</h1>
+        for value in ["a", "b", "c"] {
+            <p>(alloc::borrow::Cow::Borrowed(value))</p>
+        }
+    };
+    let mut body = std::pin::pin!(TemplateHttpBody::new(stream, 8));
+    let mut frames = Vec::new();
+    while let Some(frame) = body.frame().await {
+        frames.push(frame.unwrap().into_data().unwrap());
+    }
+    assert!(body.is_end_stream());
+    assert_eq!(body.size_hint().exact(), Some(0));
+    assert!(frames.iter().rev().skip(1).all(|frame| frame.len() >= 8));
+    assert_eq!(
+        frames.concat(),
+        b"<h1>Hello</h1><p>a</p><p>b</p><p>c</p>".as_slice()
+    );
+}
+
+#[tokio::test]
+async fn flush_when_pending() {
+    let mut state = 0;
+    let stream = futures_util::stream::poll_fn(move |cx| {
+        state += 1;
+        match state {
+            1 => Poll::Ready(Some("a")),
+            2 => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            3 => Poll::Ready(Some("b")),
+            _ => Poll::Ready(None),
+        }
+    });
+    let body = TemplateHttpBody::new(stream, 1024);
+    let mut body = std::pin::pin!(body);
+    let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+    assert_eq!(first.as_ref(), b"a");
+    let second = body.frame().await.unwrap().unwrap().into_data().unwrap();
+    assert_eq!(second.as_ref(), b"b");
+    assert!(body.frame().await.is_none());
+}
+
+// `tokio::spawn` requires `Send`, so this is what a handler on a multi-threaded runtime needs
+#[tokio::test]
+async fn send() {
+    let stream = html! {
+        for value in ["a", "b"] {
+            <p>(alloc::borrow::Cow::Borrowed(value))</p>
+        }
+    };
+    let body = TemplateHttpBody::new(stream, 1024);
+    let bytes = tokio::spawn(async move { body.collect().await.unwrap().to_bytes() })
+        .await
+        .unwrap();
+    assert_eq!(bytes.as_ref(), b"<p>a</p><p>b</p>");
+}
@@ -0,0 +1,54 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+use std::borrow::Cow;
+
+#[tokio::test]
+async fn test() {
+    let items = ["a", "bb", "ccc"];
+    let stream = html! {
+        let total = items.len();
+        <ul data-total=[(Cow::Owned(total.to_string()))]>
+            for item in items {
+                let length: usize = item.len();
+                let tx = "not the sender";
+                <li let class = if length == total { "last" } else { "item" }; class=[(Cow::Borrowed(class))]>
+                    (Cow::Borrowed(item))
+                    (Cow::Borrowed(tx))
+                </li>
+            }
+        </ul>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<ul data-total="3"><li class="item">anot the sender</li><li class="item">bbnot the sender</li><li class="last">cccnot the sender</li></ul>"#
+    )
+}
+
+#[tokio::test]
+async fn await_result() {
+    let title = async { "Title" };
+    let stream = html! {
+        let title = title.await;
+        <h1>(Cow::Borrowed(title))</h1>
+        <title>(Cow::Borrowed(title))</title>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<h1>Title</h1><title>Title</title>"#)
+}
+
+#[tokio::test]
+async fn scoped_to_element() {
+    let name = "outer";
+    let stream = html! {
+        <div>
+            let name = "inner";
+            (Cow::Borrowed(name))
+        </div>
+        (Cow::Borrowed(name))
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<div>inner</div>outer"#)
+}
@@ -0,0 +1,74 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+enum Status {
+    Active,
+    Blocked { reason: &'static str },
+    Pending(u32),
+}
+
+#[tokio::test]
+async fn test() {
+    for (status, expected) in [
+        (
+            Status::Active,
+            r#"<span class="badge active" data-status="active">Active</span>"#,
+        ),
+        (
+            Status::Blocked { reason: "spam" },
+            r#"<span class="badge blocked" title="spam">Blocked</span>"#,
+        ),
+        (
+            Status::Pending(3),
+            r#"<span class="badge pending">Pending</span>"#,
+        ),
+        (Status::Pending(0), r#"<span class="badge">Unknown</span>"#),
+    ] {
+        let stream = html! {
+            <span class=["badge" match status {
+                Status::Active => { " active" }
+                Status::Blocked { .. } => { " blocked" }
+                Status::Pending(days) if days > 0 => { " pending" },
+                Status::Pending(_) => {}
+            }] match status {
+                Status::Active => { data-status="active" }
+                Status::Blocked { reason } => { title=[(alloc::borrow::Cow::Borrowed(reason))] }
+                _ => {}
+            }>
+                match status {
+                    Status::Active => { "Active" }
+                    Status::Blocked { .. } => { "Blocked" }
+                    Status::Pending(days) if days > 0 => { "Pending" }
+                    Status::Pending(_) => { "Unknown" }
+                }
+            </span>
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+}
+
+#[tokio::test]
+async fn patterns() {
+    for (value, expected) in [
+        (Some((1, "a")), "<p>one a</p>"),
+        (Some((2, "b")), "<p>few</p>"),
+        (Some((5, "c")), "<p>many</p>"),
+        (None, "<p>none</p>"),
+    ] {
+        let stream = html! {
+            <p>
+                match value {
+                    Some((1, name)) => { "one " (alloc::borrow::Cow::Borrowed(name)) }
+                    | Some((2 | 3, _)) => { "few" }
+                    Some((4..=9, _)) => { "many" }
+                    Some(_) | None => { "none" }
+                }
+            </p>
+        };
+        let result: String = stream.collect().await;
+        assert_eq!(result, expected)
+    }
+}
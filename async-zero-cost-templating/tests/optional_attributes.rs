@@ -0,0 +1,21 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+use std::borrow::Cow;
+
+#[tokio::test]
+async fn test() {
+    let checked = true;
+    let disabled = false;
+    let placeholder = Some(Cow::Borrowed("\"name\""));
+    let title: Option<Cow<'static, str>> = None;
+    let stream = html! {
+        <input type="checkbox" checked?=(checked) disabled?=(disabled) placeholder?=(placeholder) title?=(title)>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<input type="checkbox" checked placeholder="&quot;name&quot;">"#
+    )
+}
@@ -0,0 +1,37 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+use std::borrow::Cow;
+
+#[tokio::test]
+async fn script() {
+    let name = Cow::Borrowed("</script><script>alert('x')</script>");
+    let stream = html! {
+        <script>"const name = " (name) "; if (1 < 2 && true) {}"</script>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<script>const name = "\u003C/script\u003E\u003Cscript\u003Ealert(\u0027x\u0027)\u003C/script\u003E"; if (1 < 2 && true) {}</script>"#
+    )
+}
+
+#[tokio::test]
+async fn style() {
+    let color = Cow::Borrowed("#fff");
+    let evil = Cow::Borrowed("red;}</style>");
+    let stream = html! {
+        <style>
+            "p { color: " (color) "; }"
+            if true {
+                "a { color: " (evil) "; }"
+            }
+        </style>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<style>p { color: #fff; }a { color: red\3B \7D \3C \2F style\3E ; }</style>"#
+    )
+}
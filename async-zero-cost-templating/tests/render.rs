@@ -0,0 +1,48 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, Displayed, Render};
+use futures_util::stream::StreamExt;
+use std::{borrow::Cow, net::Ipv4Addr, sync::Arc};
+
+struct User {
+    name: &'static str,
+}
+
+impl<'a> Render<'a> for &'a User {
+    fn render(self) -> Cow<'a, str> {
+        Cow::Borrowed(self.name)
+    }
+}
+
+#[tokio::test]
+async fn test() {
+    let owned = String::from("<name>");
+    let name = &owned;
+    let shared: Arc<str> = Arc::from("shared & owned");
+    let missing: Option<u32> = None;
+    let user = &User { name: "<user>" };
+    let stream = html! {
+        <ul>
+            <li>(42u8) (-7i64) (1.5f64) ('<')</li>
+            <li>("borrowed") (name) (name.clone()) (shared)</li>
+            <li>(Some(1)) (missing)</li>
+            <li>(Displayed(Ipv4Addr::LOCALHOST)) (user)</li>
+        </ul>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<ul><li>42-71.5&lt;</li><li>borrowed&lt;name&gt;&lt;name&gt;shared &amp; owned</li><li>1</li><li>127.0.0.1&lt;user&gt;</li></ul>"#
+    )
+}
+
+#[tokio::test]
+async fn references() {
+    let count = 3u32;
+    let values = vec![1.5f32, -2.0];
+    let stream = html! {
+        <p>(&count) for value in &values { " " (value) }</p>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, "<p>3 1.5 -2</p>")
+}
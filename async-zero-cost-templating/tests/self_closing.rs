@@ -0,0 +1,32 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+
+#[tokio::test]
+async fn test() {
+    let stream = html! {
+        <p>"a"<br/>"b"</p>
+        <img src="a.png" alt="" />
+        <svg viewBox="0 0 10 10">
+            <path d="M0 0L10 10" />
+            <circle r="1"/>
+        </svg>
+        <math><mspace width="1em"/></math>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<p>a<br>b</p><img src="a.png" alt=""><svg viewBox="0 0 10 10"><path d="M0 0L10 10"/><circle r="1"/></svg><math><mspace width="1em"/></math>"#
+    )
+}
+
+#[tokio::test]
+async fn partial() {
+    // foreign elements can be rendered by a template that is inserted into an `<svg>`
+    let stream = html! {
+        <rect width="1" height="1" />
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<rect width="1" height="1"/>"#)
+}
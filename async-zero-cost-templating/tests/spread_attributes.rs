@@ -0,0 +1,34 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+use futures_util::stream::StreamExt;
+use std::{borrow::Cow, collections::BTreeMap};
+
+#[tokio::test]
+async fn test() {
+    let data = BTreeMap::from([("data-id", "1"), ("data-name", r#""quoted" & <b>"#)]);
+    let hx = vec![(String::from("hx-get"), Cow::Borrowed("/rows?page=2"))];
+    let stream = html! {
+        <div class="rows" ..(data.iter().map(|(name, value)| (*name, Cow::Borrowed(*value)))) ..(hx)>
+        </div>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(
+        result,
+        r#"<div class="rows" data-id="1" data-name="&quot;quoted&quot; &amp; &lt;b&gt;" hx-get="/rows?page=2"></div>"#
+    )
+}
+
+#[tokio::test]
+async fn invalid_name() {
+    let attributes = [
+        ("onclick=\"alert(1)\" x", Cow::Borrowed("")),
+        ("", Cow::Borrowed("empty")),
+        ("data-id", Cow::Borrowed("1")),
+    ];
+    let stream = html! {
+        <div ..(attributes)></div>
+    };
+    let result: String = stream.collect().await;
+    assert_eq!(result, r#"<div data-id="1"></div>"#)
+}
@@ -0,0 +1,12 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, html_attributes};
+
+pub fn main() {
+    let inner = html_attributes! {
+        class="bold"
+    };
+    let _ = html! {
+        <a href=[{ inner }]></a>
+    };
+}
@@ -0,0 +1,9 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <input value=true>
+    };
+}
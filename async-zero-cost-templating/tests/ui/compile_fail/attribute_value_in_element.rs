@@ -0,0 +1,12 @@
+extern crate alloc;
+
+use async_zero_cost_templating::{html, html_attribute_value};
+
+pub fn main() {
+    let inner = html_attribute_value! {
+        "bold"
+    };
+    let _ = html! {
+        <p>{ inner }</p>
+    };
+}
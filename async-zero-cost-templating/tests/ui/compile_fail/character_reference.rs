@@ -0,0 +1,11 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <p>"Tom &amp; Jerry" r"a&nbps;b"</p>
+        <p>r"&#xZZ;" r"&#;"</p>
+        <p title=r"&#xD800;">r"&#169; &#xA9;"</p>
+    };
+}
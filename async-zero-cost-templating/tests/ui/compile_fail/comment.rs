@@ -0,0 +1,12 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <script><!-- "hidden" --></script>
+    };
+    let _ = html! {
+        <!-- <b>"bold"</b> -->
+    };
+}
@@ -0,0 +1,10 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <my-widget>
+        </my-gadget>
+    };
+}
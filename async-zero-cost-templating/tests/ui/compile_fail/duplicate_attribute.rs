@@ -0,0 +1,15 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let condition = true;
+    let _ = html! {
+        <input class="a" class="b">
+        <div id="a" if condition { id="b" }></div>
+        <div if condition { title="a" } else { title="b" } title="c"></div>
+        <div if condition { lang="a" } else { lang="b" }></div>
+        <div for _ in 0..2 { class="a" }></div>
+        <div while condition { if condition { hidden } }></div>
+    };
+}
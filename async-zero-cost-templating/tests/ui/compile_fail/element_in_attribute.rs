@@ -0,0 +1,12 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let inner = html! {
+        <b>"bold"</b>
+    };
+    let _ = html! {
+        <a { inner }></a>
+    };
+}
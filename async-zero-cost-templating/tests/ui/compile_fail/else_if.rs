@@ -0,0 +1,16 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let value = 1;
+    let _ = html! {
+        if value == 0 {
+            "zero"
+        } else if value == 1 {
+            <p>"one"
+        } else {
+            "other"
+        }
+    };
+}
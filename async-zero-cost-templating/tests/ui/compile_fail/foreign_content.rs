@@ -0,0 +1,16 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <svg viewbox="0 0 10 10">
+            <lineargradient></lineargradient>
+            <div>"html"</div>
+            <use xlink:hraf="#icon" />
+        </svg>
+        <math><p>"text"</p></math>
+        <a xlink:href="#icon">"link"</a>
+        <p xml:lang="en">"text"</p>
+    };
+}
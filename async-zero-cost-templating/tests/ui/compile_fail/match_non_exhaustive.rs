@@ -0,0 +1,13 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let value = Some(1);
+    let _ = html! {
+        match value {
+            Some(1) => { "one" }
+            None => { "none" }
+        }
+    };
+}
@@ -0,0 +1,16 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let inner = html! {
+        "alert(1)"
+    };
+    let _ = html! {
+        <script>
+            <b>"bold"</b>
+            { inner }
+            "</script>"
+        </script>
+    };
+}
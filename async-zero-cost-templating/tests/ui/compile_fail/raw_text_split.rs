@@ -0,0 +1,18 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let condition = true;
+    let _ = html! {
+        <script>"var a = \"<" "/script><b>x</b>\";"</script>
+        <style>
+            "a { color: red; }<" "/"
+            if condition { "sty" } else { "STY" }
+            "le>"
+        </style>
+        <script>
+            for _ in 0..2 { "ipt>" "<" "/scr" }
+        </script>
+    };
+}
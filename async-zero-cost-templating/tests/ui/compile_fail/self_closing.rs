@@ -0,0 +1,15 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <div/>
+        <my-widget />
+        <svg>
+            <foreignObject>
+                <span/>
+            </foreignObject>
+        </svg>
+    };
+}
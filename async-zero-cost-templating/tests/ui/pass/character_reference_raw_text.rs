@@ -0,0 +1,11 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <script>"s = '&amp;'" r"a&b;"</script>
+        <style>r"a::after { content: '&nbsp;x;' }"</style>
+        <!-- "&amp;" r"&unknown;" -->
+    };
+}
@@ -0,0 +1,11 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <button hx-post="/rows" hx-on:click="reset()" x-on:click="open = true" v-bind:class="classes">
+            "Load"
+        </button>
+    };
+}
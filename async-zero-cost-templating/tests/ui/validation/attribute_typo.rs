@@ -0,0 +1,13 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <a hfer="/">"home"</a>
+        <input tpye="text" data-id="1" aria-label="Name">
+        <div if true { class="a" } else { clas="b" }></div>
+        <my-element anything="goes"></my-element>
+        <span frobnicate="yes"></span>
+    };
+}
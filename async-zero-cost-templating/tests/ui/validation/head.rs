@@ -0,0 +1,13 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <head>
+            <meta charset="utf-8">
+            <title>"Title"</title>
+            <div>"content"</div>
+        </head>
+    };
+}
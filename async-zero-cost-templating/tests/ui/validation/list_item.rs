@@ -0,0 +1,11 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <div>
+            <li>"item"</li>
+        </div>
+    };
+}
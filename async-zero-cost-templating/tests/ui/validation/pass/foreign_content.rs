@@ -0,0 +1,14 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <svg viewBox="0 0 10 10">
+            <title>"icon"</title>
+            <g>
+                <title>"group"</title>
+            </g>
+        </svg>
+    };
+}
@@ -0,0 +1,11 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <button hx-post="/rows" hx-target="#rows" x-data="{ open: false }" data-id="1" aria-label="Load">
+            "Load"
+        </button>
+    };
+}
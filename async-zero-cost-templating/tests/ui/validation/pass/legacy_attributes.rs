@@ -0,0 +1,18 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <meta property="og:title" content="Title">
+        <div vocab="https://schema.org/" typeof="Person" about="#me" resource="#me" prefix="og: https://ogp.me/ns#"></div>
+        <table border="0" cellpadding="0" cellspacing="0" width="100%">
+            <tbody>
+                <tr>
+                    <td align="center" valign="top" bgcolor="#ffffff">"email"</td>
+                </tr>
+            </tbody>
+        </table>
+        <span r#frobnicate="yes"></span>
+    };
+}
@@ -0,0 +1,16 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <template id="row">
+            <tr>
+                <td>"cell"</td>
+            </tr>
+        </template>
+        <template id="item">
+            <li>"item"</li>
+        </template>
+    };
+}
@@ -0,0 +1,14 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <p>
+            "text"
+            if true {
+                <div>"block"</div>
+            }
+        </p>
+    };
+}
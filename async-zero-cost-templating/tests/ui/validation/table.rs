@@ -0,0 +1,13 @@
+extern crate alloc;
+
+use async_zero_cost_templating::html;
+
+pub fn main() {
+    let _ = html! {
+        <table>
+            <tr>
+                <td>"cell"</td>
+            </tr>
+        </table>
+    };
+}